@@ -0,0 +1,238 @@
+use anchor_lang::prelude::AccountMeta;
+use raydium_amm_v3::states::{PersonalPositionState, PoolState, TickArrayState};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{pubkey::Pubkey, signature::Signature, signer::Signer};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+
+use crate::create_token_account::create_missing_ata_instrs;
+use crate::decrease_liquidity::{
+    decrease_liquidity_instr, get_reward_mints, reward_remaining_accounts,
+};
+use crate::positions::{get_position_fees, get_tick_state, PositionFees, PositionReward};
+use crate::utils::deserialize_anchor_account;
+use crate::utils::find_position;
+use crate::utils::get_anchor_account;
+use crate::utils::get_pool_mints_transfer_fee;
+use crate::utils::get_pool_positions_by_owner;
+use crate::utils::get_tick_array_bitmap;
+use crate::utils::get_tick_array_key;
+use crate::utils::PositionNftTokenInfo;
+use crate::utils::{get_transfer_fee, unpack_mint};
+use crate::{
+    config::Config,
+    error::{ClientError, Result},
+    rpc::send_txn,
+    utils::{read_keypair_file, PositionSelector},
+};
+
+/// Fees and rewards paid out to the owner by a collect transaction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CollectResult {
+    pub nft_mint: Pubkey,
+    pub signature: Signature,
+    /// Amounts paid out to the owner's token accounts, net of Token-2022 transfer fees, as
+    /// owed when the transaction was built. Rewards emitted since the pool last updated
+    /// its reward growth are not included.
+    pub amount_0: u64,
+    pub amount_1: u64,
+    pub rewards: Vec<PositionReward>,
+}
+
+/// Collects the fees and rewards of the position held in `nft_info` by decreasing
+/// its liquidity by zero.
+fn collect_position(
+    config: &Config,
+    rpc_client: &RpcClient,
+    pool_pubkey: Pubkey,
+    pool: &PoolState,
+    position: &PersonalPositionState,
+    nft_info: &PositionNftTokenInfo,
+) -> Result<CollectResult> {
+    let payer = read_keypair_file(&config.global.payer_path)?;
+
+    let transfer_fee =
+        get_pool_mints_transfer_fee(rpc_client, pool.token_mint_0, pool.token_mint_1, 0, 0)?;
+    let user_token_account_0 = get_associated_token_address_with_program_id(
+        &payer.pubkey(),
+        &pool.token_mint_0,
+        &transfer_fee.0.owner,
+    );
+    let user_token_account_1 = get_associated_token_address_with_program_id(
+        &payer.pubkey(),
+        &pool.token_mint_1,
+        &transfer_fee.1.owner,
+    );
+
+    let reward_mints = get_reward_mints(rpc_client, pool)?;
+
+    let program_id = config.raydium_v3_program_id()?;
+    let tickarray_bitmap_extension = get_tick_array_bitmap(
+        &pool.amm_config,
+        &pool.token_mint_0,
+        &pool.token_mint_1,
+        &program_id,
+    );
+    let mut remaining_accounts = Vec::new();
+    remaining_accounts.push(AccountMeta::new(tickarray_bitmap_extension, false));
+    remaining_accounts.extend(reward_remaining_accounts(
+        pool,
+        &payer.pubkey(),
+        &reward_mints,
+    ));
+
+    // create the token accounts receiving the fees and rewards if needed
+    let mut ata_mints = vec![
+        (pool.token_mint_0, transfer_fee.0.owner),
+        (pool.token_mint_1, transfer_fee.1.owner),
+    ];
+    ata_mints.extend(reward_mints.iter().copied());
+    let mut instructions =
+        create_missing_ata_instrs(rpc_client, &payer.pubkey(), &payer.pubkey(), &ata_mints)?;
+    instructions.extend(decrease_liquidity_instr(
+        config,
+        &payer,
+        pool_pubkey,
+        pool.token_vault_0,
+        pool.token_vault_1,
+        pool.token_mint_0,
+        pool.token_mint_1,
+        position.nft_mint,
+        nft_info.key,
+        user_token_account_0,
+        user_token_account_1,
+        remaining_accounts,
+        0,
+        0,
+        0,
+        position.tick_lower_index,
+        position.tick_upper_index,
+        TickArrayState::get_array_start_index(position.tick_lower_index, pool.tick_spacing.into()),
+        TickArrayState::get_array_start_index(position.tick_upper_index, pool.tick_spacing.into()),
+    )?);
+
+    // the transaction pays out what the position owes when it is built
+    let owed = get_position_owed(rpc_client, &program_id, pool_pubkey, pool, position)?;
+    let mut mints = vec![pool.token_mint_0, pool.token_mint_1];
+    mints.extend(reward_mints.iter().map(|(mint, _)| *mint));
+    let mut amounts = vec![owed.fees_owed_0, owed.fees_owed_1];
+    let reward_infos = pool.reward_infos;
+    amounts.extend(
+        reward_infos
+            .iter()
+            .zip(owed.rewards_owed)
+            .filter(|(reward_info, _)| reward_info.token_mint != Pubkey::default())
+            .map(|(_, amount)| amount),
+    );
+    let collected = amounts_net_of_transfer_fee(rpc_client, &mints, &amounts)?;
+
+    // send
+    let signers = vec![&payer];
+    let result = send_txn(config, rpc_client, &instructions, &payer, &signers)?;
+
+    Ok(CollectResult {
+        nft_mint: position.nft_mint,
+        signature: result.signature,
+        amount_0: collected[0],
+        amount_1: collected[1],
+        rewards: reward_mints
+            .into_iter()
+            .zip(&collected[2..])
+            .map(|((mint, _), amount)| PositionReward {
+                mint,
+                amount: *amount,
+            })
+            .collect(),
+    })
+}
+
+/// Returns the fees and rewards `position` owes, from the tick arrays holding its bounds.
+fn get_position_owed(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    pool_pubkey: Pubkey,
+    pool: &PoolState,
+    position: &PersonalPositionState,
+) -> Result<PositionFees> {
+    let tick_indexes = [position.tick_lower_index, position.tick_upper_index];
+    let tick_array_keys: Vec<Pubkey> = tick_indexes
+        .iter()
+        .map(|tick_index| {
+            let start_index =
+                TickArrayState::get_array_start_index(*tick_index, pool.tick_spacing.into());
+            get_tick_array_key(&pool_pubkey, start_index, program_id)
+        })
+        .collect();
+    let rsps = rpc_client.get_multiple_accounts(&tick_array_keys)?;
+    let mut ticks = Vec::with_capacity(2);
+    for ((tick_index, key), rsp) in tick_indexes.iter().zip(tick_array_keys).zip(rsps) {
+        let account = rsp.ok_or(ClientError::AccountNotFound(key))?;
+        let tick_array = deserialize_anchor_account::<TickArrayState>(&account)?;
+        ticks.push(get_tick_state(&tick_array, *tick_index, pool.tick_spacing)?);
+    }
+    get_position_fees(pool, position, &ticks[0], &ticks[1])
+}
+
+/// Returns `amounts` of `mints` net of their Token-2022 transfer fees.
+fn amounts_net_of_transfer_fee(
+    rpc_client: &RpcClient,
+    mints: &[Pubkey],
+    amounts: &[u64],
+) -> Result<Vec<u64>> {
+    let epoch = rpc_client.get_epoch_info()?.epoch;
+    let rsps = rpc_client.get_multiple_accounts(mints)?;
+    mints
+        .iter()
+        .zip(rsps)
+        .zip(amounts)
+        .map(|((mint, rsp), amount)| {
+            let account = rsp.ok_or(ClientError::AccountNotFound(*mint))?;
+            let state = unpack_mint(mint, &account)?;
+            Ok(amount.saturating_sub(get_transfer_fee(&state, epoch, *amount)?))
+        })
+        .collect()
+}
+
+/// Collects the trading fees and rewards of a position without removing liquidity.
+///
+/// When `position_nft` is `None`, every position the payer holds in the pool is swept,
+/// one transaction per position.
+pub fn collect(
+    config: &Config,
+    pool_pubkey: Pubkey,
+    position_nft: Option<Pubkey>,
+) -> Result<Vec<CollectResult>> {
+    let payer = read_keypair_file(&config.global.payer_path)?;
+    let rpc_client = config.rpc_client()?;
+    let program_id = config.raydium_v3_program_id()?;
+
+    let pool: PoolState = get_anchor_account(&rpc_client, &pool_pubkey)?;
+
+    let positions = match position_nft {
+        Some(nft_mint) => find_position(
+            &rpc_client,
+            &payer.pubkey(),
+            &program_id,
+            &pool_pubkey,
+            &pool,
+            &PositionSelector::NftMint(nft_mint),
+        )?
+        .into_iter()
+        .collect(),
+        None => {
+            get_pool_positions_by_owner(&rpc_client, &payer.pubkey(), &program_id, &pool_pubkey)?
+        }
+    };
+    if positions.is_empty() {
+        return Err(ClientError::PositionNotFound(format!(
+            "no position in pool {}",
+            pool_pubkey
+        )));
+    }
+
+    positions
+        .iter()
+        .map(|(position, nft_info)| {
+            collect_position(config, &rpc_client, pool_pubkey, &pool, position, nft_info)
+        })
+        .collect()
+}
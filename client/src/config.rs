@@ -1,7 +1,28 @@
 use serde::Deserialize;
-use std::error::Error;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use std::collections::HashMap;
+use std::env;
 use std::fs;
 use std::path::Path;
+use std::str::FromStr;
+
+use crate::error::{ClientError, Result};
+
+pub const ENV_HTTP_URL: &str = "RAYDIUM_HTTP_URL";
+pub const ENV_WS_URL: &str = "RAYDIUM_WS_URL";
+pub const ENV_PAYER_PATH: &str = "RAYDIUM_PAYER_PATH";
+pub const ENV_RAYDIUM_V3_PROGRAM: &str = "RAYDIUM_V3_PROGRAM";
+pub const ENV_SLIPPAGE: &str = "RAYDIUM_SLIPPAGE";
+pub const ENV_COMMITMENT: &str = "RAYDIUM_COMMITMENT";
+
+fn default_commitment() -> String {
+    "confirmed".to_string()
+}
+
+fn default_wrap_sol() -> bool {
+    true
+}
 
 #[derive(Deserialize, Debug)]
 pub struct Global {
@@ -10,18 +31,222 @@ pub struct Global {
     pub payer_path: String,
     pub raydium_v3_program: String,
     pub slippage: f64,
+    /// Commitment transactions are confirmed at: `processed`, `confirmed` or `finalized`.
+    #[serde(default = "default_commitment")]
+    pub commitment: String,
+    /// Simulate transactions instead of sending them.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Wrap native SOL into a temporary WSOL account for the operations on pools paired
+    /// with SOL, instead of using the payer's WSOL associated token account.
+    #[serde(default = "default_wrap_sol")]
+    pub wrap_sol: bool,
+}
+
+/// A partial `Global` section. Used for named profiles, environment variables
+/// and command-line overrides, each replacing only the values it sets.
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct GlobalOverrides {
+    pub http_url: Option<String>,
+    pub ws_url: Option<String>,
+    pub payer_path: Option<String>,
+    pub raydium_v3_program: Option<String>,
+    pub slippage: Option<f64>,
+    pub commitment: Option<String>,
+    pub dry_run: Option<bool>,
+    pub wrap_sol: Option<bool>,
+}
+
+impl GlobalOverrides {
+    /// Reads the overrides from the `RAYDIUM_*` environment variables.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(&env::vars().collect())
+    }
+
+    /// Reads the overrides from `vars`, a map of `RAYDIUM_*` variable names to values.
+    pub fn from_vars(vars: &HashMap<String, String>) -> Result<Self> {
+        let slippage = match vars.get(ENV_SLIPPAGE) {
+            Some(slippage) => Some(slippage.parse().map_err(|e| {
+                ClientError::Config(format!("invalid {} {}: {}", ENV_SLIPPAGE, slippage, e))
+            })?),
+            None => None,
+        };
+        Ok(Self {
+            http_url: vars.get(ENV_HTTP_URL).cloned(),
+            ws_url: vars.get(ENV_WS_URL).cloned(),
+            payer_path: vars.get(ENV_PAYER_PATH).cloned(),
+            raydium_v3_program: vars.get(ENV_RAYDIUM_V3_PROGRAM).cloned(),
+            slippage,
+            commitment: vars.get(ENV_COMMITMENT).cloned(),
+            dry_run: None,
+            wrap_sol: None,
+        })
+    }
+}
+
+impl Global {
+    fn apply(&mut self, overrides: &GlobalOverrides) {
+        if let Some(http_url) = &overrides.http_url {
+            self.http_url = http_url.clone();
+        }
+        if let Some(ws_url) = &overrides.ws_url {
+            self.ws_url = ws_url.clone();
+        }
+        if let Some(payer_path) = &overrides.payer_path {
+            self.payer_path = payer_path.clone();
+        }
+        if let Some(raydium_v3_program) = &overrides.raydium_v3_program {
+            self.raydium_v3_program = raydium_v3_program.clone();
+        }
+        if let Some(slippage) = overrides.slippage {
+            self.slippage = slippage;
+        }
+        if let Some(commitment) = &overrides.commitment {
+            self.commitment = commitment.clone();
+        }
+        if let Some(dry_run) = overrides.dry_run {
+            self.dry_run = dry_run;
+        }
+        if let Some(wrap_sol) = overrides.wrap_sol {
+            self.wrap_sol = wrap_sol;
+        }
+    }
+}
+
+/// Compute budget applied to every transaction, from the `[priority_fee]` section.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct PriorityFee {
+    /// Price of a compute unit in micro-lamports. 0 disables the priority fee.
+    pub compute_unit_price: u64,
+    /// Fixed compute unit limit. When unset, it is derived from a simulation.
+    pub compute_unit_limit: Option<u32>,
+    /// Fraction added on top of the simulated compute units.
+    pub compute_unit_margin: f64,
+}
+
+impl Default for PriorityFee {
+    fn default() -> Self {
+        Self {
+            compute_unit_price: 0,
+            compute_unit_limit: None,
+            compute_unit_margin: 0.1,
+        }
+    }
 }
 
 #[derive(Deserialize, Debug)]
 pub struct Config {
     pub global: Global,
+    #[serde(default)]
+    pub priority_fee: PriorityFee,
+    /// Named profiles (`[profiles.devnet]`, `[profiles.mainnet]`, ...) layered over `global`.
+    #[serde(default)]
+    pub profiles: HashMap<String, GlobalOverrides>,
 }
 
 impl Config {
     /// Loads and parses the configuration from a TOML file at the given path.
-    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
-        let content = fs::read_to_string(path)?;
-        let config: Config = toml::from_str(&content)?;
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let config = Self::parse_file(path)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn parse_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = fs::read_to_string(&path).map_err(|e| {
+            ClientError::Config(format!("failed to read {}: {}", path.as_ref().display(), e))
+        })?;
+        let config: Config = toml::from_str(&content).map_err(|e| {
+            ClientError::Config(format!(
+                "failed to parse {}: {}",
+                path.as_ref().display(),
+                e
+            ))
+        })?;
+        Ok(config)
+    }
+
+    /// Loads the configuration file, then layers the selected profile, the
+    /// environment variables and the command-line overrides on top, in that order.
+    pub fn load<P: AsRef<Path>>(
+        path: P,
+        profile: Option<&str>,
+        overrides: &GlobalOverrides,
+    ) -> Result<Self> {
+        let mut config = Self::parse_file(path)?;
+        if let Some(profile) = profile {
+            config.apply_profile(profile)?;
+        }
+        config.global.apply(&GlobalOverrides::from_env()?);
+        config.global.apply(overrides);
+        config.validate()?;
         Ok(config)
     }
+
+    /// Overlays `overrides` on the `global` section, keeping the values they do not set.
+    pub fn apply_overrides(&mut self, overrides: &GlobalOverrides) {
+        self.global.apply(overrides);
+    }
+
+    /// Overlays the named profile on the `global` section.
+    pub fn apply_profile(&mut self, profile: &str) -> Result<()> {
+        let overrides = self
+            .profiles
+            .get(profile)
+            .cloned()
+            .ok_or_else(|| ClientError::Config(format!("unknown profile {}", profile)))?;
+        self.global.apply(&overrides);
+        Ok(())
+    }
+
+    /// Checks that the program id and commitment parse, the slippage is within 0..1
+    /// and the compute unit margin is not negative.
+    pub fn validate(&self) -> Result<()> {
+        self.raydium_v3_program_id()?;
+        self.commitment()?;
+        if !(0.0..1.0).contains(&self.global.slippage) {
+            return Err(ClientError::Config(format!(
+                "slippage {} is not within 0..1",
+                self.global.slippage
+            )));
+        }
+        let compute_unit_margin = self.priority_fee.compute_unit_margin;
+        if compute_unit_margin.is_nan() || compute_unit_margin < 0.0 {
+            return Err(ClientError::Config(format!(
+                "compute_unit_margin {} is negative",
+                compute_unit_margin
+            )));
+        }
+        Ok(())
+    }
+
+    /// Parses the configured Raydium CLMM program id.
+    pub fn raydium_v3_program_id(&self) -> Result<Pubkey> {
+        self.global.raydium_v3_program.parse().map_err(|e| {
+            ClientError::Config(format!(
+                "invalid raydium_v3_program {}: {}",
+                self.global.raydium_v3_program, e
+            ))
+        })
+    }
+
+    /// Parses the configured commitment.
+    pub fn commitment(&self) -> Result<CommitmentConfig> {
+        CommitmentConfig::from_str(&self.global.commitment).map_err(|e| {
+            ClientError::Config(format!(
+                "invalid commitment {}: {}",
+                self.global.commitment, e
+            ))
+        })
+    }
+
+    /// Builds an RPC client for the configured cluster, reading at the configured
+    /// commitment so that reads see the transactions `send_txn` confirmed.
+    pub fn rpc_client(&self) -> Result<RpcClient> {
+        Ok(RpcClient::new_with_commitment(
+            self.global.http_url.to_string(),
+            self.commitment()?,
+        ))
+    }
 }
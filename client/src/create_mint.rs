@@ -1,34 +1,86 @@
 use anchor_client::{Client, Cluster};
-use anyhow::Result;
 use rand::rngs::OsRng;
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::{
-    instruction::Instruction, program_pack::Pack, pubkey::Pubkey, signature::Keypair,
-    signer::Signer, system_instruction, transaction::Transaction,
+    instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer,
+    system_instruction,
+};
+use spl_token_2022::extension::{
+    interest_bearing_mint, metadata_pointer, transfer_fee, ExtensionType,
 };
 use spl_token_2022::state::Mint;
 
-use crate::{config::Config, rpc::send_txn, utils::read_keypair_file};
+use crate::{
+    config::Config,
+    error::{ClientError, Result},
+    rpc::send_txn,
+    utils::read_keypair_file,
+};
+
+/// A Token-2022 mint extension initialized together with the mint.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MintExtension {
+    /// Transfer fee of `basis_points`, capped at `maximum_fee`.
+    TransferFee { basis_points: u16, maximum_fee: u64 },
+    /// Interest accruing at `rate` basis points per year.
+    InterestBearing { rate: i16 },
+    /// Pointer to the account holding the token metadata, the mint itself by default.
+    MetadataPointer { metadata_address: Option<Pubkey> },
+    /// Tokens can not be transferred once minted.
+    NonTransferable,
+}
+
+impl MintExtension {
+    pub fn extension_type(&self) -> ExtensionType {
+        match self {
+            MintExtension::TransferFee { .. } => ExtensionType::TransferFeeConfig,
+            MintExtension::InterestBearing { .. } => ExtensionType::InterestBearingConfig,
+            MintExtension::MetadataPointer { .. } => ExtensionType::MetadataPointer,
+            MintExtension::NonTransferable => ExtensionType::NonTransferable,
+        }
+    }
+}
 
-pub fn create_mint(config: &Config) -> Result<Pubkey> {
-    let payer = read_keypair_file(&config.global.payer_path).unwrap();
-    let rpc_client = RpcClient::new(config.global.http_url.to_string());
+/// Parameters of a new mint.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MintOptions {
+    pub decimals: u8,
+    /// Mint authority, the payer by default. Also the authority of the extensions.
+    pub mint_authority: Option<Pubkey>,
+    pub freeze_authority: Option<Pubkey>,
+    /// `spl_token::id()` or `spl_token_2022::id()`.
+    pub token_program: Pubkey,
+    /// Extensions, only supported by Token-2022.
+    pub extensions: Vec<MintExtension>,
+}
+
+impl Default for MintOptions {
+    fn default() -> Self {
+        Self {
+            decimals: 0,
+            mint_authority: None,
+            freeze_authority: None,
+            token_program: spl_token_2022::id(),
+            extensions: Vec::new(),
+        }
+    }
+}
+
+pub fn create_mint(config: &Config, options: &MintOptions) -> Result<Pubkey> {
+    let payer = read_keypair_file(&config.global.payer_path)?;
+    let rpc_client = config.rpc_client()?;
 
-    let authority = payer.pubkey();
     let mint = Keypair::generate(&mut OsRng);
     let create_and_init_instr =
-        create_and_init_mint_instr(&config, &payer, &mint.pubkey(), &authority, 0)?;
+        create_and_init_mint_instr(&config, &payer, &mint.pubkey(), options)?;
     // send
     let signers = vec![&payer, &mint];
-    let recent_hash = rpc_client.get_latest_blockhash()?;
-    let txn = Transaction::new_signed_with_payer(
+    let _ = send_txn(
+        config,
+        &rpc_client,
         &create_and_init_instr,
-        Some(&payer.pubkey()),
+        &payer,
         &signers,
-        recent_hash,
-    );
-
-    let _ = send_txn(&rpc_client, &txn, true)?;
+    )?;
 
     Ok(mint.pubkey())
 }
@@ -37,15 +89,33 @@ pub fn create_and_init_mint_instr(
     config: &Config,
     payer: &Keypair,
     mint_key: &Pubkey,
-    mint_authority: &Pubkey,
-    decimals: u8,
+    options: &MintOptions,
 ) -> Result<Vec<Instruction>> {
+    let token_program = options.token_program;
+    if token_program != spl_token::id() && token_program != spl_token_2022::id() {
+        return Err(ClientError::InvalidArgument(format!(
+            "{} is not a token program",
+            token_program
+        )));
+    }
+    if token_program == spl_token::id() && !options.extensions.is_empty() {
+        return Err(ClientError::InvalidArgument(
+            "mint extensions require the Token-2022 program".to_string(),
+        ));
+    }
+
     let url = Cluster::Custom(config.global.http_url.clone(), config.global.ws_url.clone());
     let client = Client::new(url, payer);
 
-    let program = client.program(spl_token_2022::id())?;
+    let program = client.program(token_program)?;
+    let mint_authority = options.mint_authority.unwrap_or_else(|| payer.pubkey());
 
-    let space = Mint::LEN;
+    let extension_types: Vec<ExtensionType> = options
+        .extensions
+        .iter()
+        .map(MintExtension::extension_type)
+        .collect();
+    let space = ExtensionType::try_calculate_account_len::<Mint>(&extension_types)?;
 
     let mut instructions = vec![system_instruction::create_account(
         &program.payer(),
@@ -57,13 +127,62 @@ pub fn create_and_init_mint_instr(
         &program.id(),
     )];
 
-    instructions.push(spl_token_2022::instruction::initialize_mint(
-        &program.id(),
-        mint_key,
-        mint_authority,
-        None,
-        decimals,
-    )?);
+    // Extensions must be initialized before the mint itself.
+    for extension in &options.extensions {
+        instructions.push(match *extension {
+            MintExtension::TransferFee {
+                basis_points,
+                maximum_fee,
+            } => transfer_fee::instruction::initialize_transfer_fee_config(
+                &program.id(),
+                mint_key,
+                Some(&mint_authority),
+                Some(&mint_authority),
+                basis_points,
+                maximum_fee,
+            )?,
+            MintExtension::InterestBearing { rate } => {
+                interest_bearing_mint::instruction::initialize(
+                    &program.id(),
+                    mint_key,
+                    Some(mint_authority),
+                    rate,
+                )?
+            }
+            MintExtension::MetadataPointer { metadata_address } => {
+                metadata_pointer::instruction::initialize(
+                    &program.id(),
+                    mint_key,
+                    Some(mint_authority),
+                    Some(metadata_address.unwrap_or(*mint_key)),
+                )?
+            }
+            MintExtension::NonTransferable => {
+                spl_token_2022::instruction::initialize_non_transferable_mint(
+                    &program.id(),
+                    mint_key,
+                )?
+            }
+        });
+    }
+
+    instructions.push(if token_program == spl_token::id() {
+        spl_token::instruction::initialize_mint(
+            &program.id(),
+            mint_key,
+            &mint_authority,
+            options.freeze_authority.as_ref(),
+            options.decimals,
+        )?
+    } else {
+        spl_token_2022::instruction::initialize_mint(
+            &program.id(),
+            mint_key,
+            &mint_authority,
+            options.freeze_authority.as_ref(),
+            options.decimals,
+        )?
+    });
 
     Ok(instructions)
 }
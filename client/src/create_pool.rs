@@ -1,105 +1,248 @@
+use std::collections::HashMap;
+
 use anchor_client::{Client, Cluster};
-use anyhow::Result;
 use raydium_amm_v3::accounts as raydium_accounts;
 use raydium_amm_v3::instruction as raydium_instruction;
 use raydium_amm_v3::{
     libraries::tick_math,
-    states::{OBSERVATION_SEED, POOL_SEED, POOL_VAULT_SEED},
+    states::{AmmConfig, OBSERVATION_SEED},
 };
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::{
-    instruction::Instruction, program_pack::Pack, pubkey::Pubkey, signature::Keypair,
-    signer::Signer, transaction::Transaction,
+    account::Account, instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer,
 };
 use solana_sdk::{system_program, sysvar};
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType};
 
+use crate::utils::deserialize_anchor_account;
 use crate::utils::get_tick_array_bitmap;
+use crate::utils::{get_amm_config_key, get_pool_key, get_pool_vault_key, sort_mints};
+use crate::utils::{price_inverted, quoted_price_to_sqrt_price_x64};
 use crate::{
     config::Config,
+    error::{ClientError, Result},
+    price::{check_price, sqrt_price_x64_to_price},
     rpc::send_txn,
-    utils::{price_to_sqrt_price_x64, read_keypair_file},
+    utils::{read_keypair_file, unpack_mint},
 };
 
+/// Token-2022 mint extensions the CLMM program accepts in a pool.
+pub const SUPPORTED_MINT_EXTENSIONS: [ExtensionType; 4] = [
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+    ExtensionType::InterestBearingConfig,
+];
+
+/// A validated create-pool request, with the mints in pool order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreatePoolParams {
+    pub mint0: Pubkey,
+    pub mint1: Pubkey,
+    pub token_program_0: Pubkey,
+    pub token_program_1: Pubkey,
+    pub decimals_0: u8,
+    pub decimals_1: u8,
+    pub amm_config: Pubkey,
+    pub pool: Pubkey,
+    /// Price of mint0 in mint1 at `sqrt_price_x64`, inverted if the mints were given in
+    /// the other order.
+    pub price: String,
+    pub sqrt_price_x64: u128,
+    pub tick: i32,
+}
+
+/// Checks a mint account, returning its token program and decimals.
+fn check_pool_mint(
+    mint: &Pubkey,
+    account: Option<&Account>,
+    problems: &mut Vec<String>,
+) -> Option<(Pubkey, u8)> {
+    let account = match account {
+        Some(account) => account,
+        None => {
+            problems.push(format!("mint {} does not exist", mint));
+            return None;
+        }
+    };
+    if account.owner != spl_token::id() && account.owner != spl_token_2022::id() {
+        problems.push(format!(
+            "mint {} is owned by {}, not by a token program",
+            mint, account.owner
+        ));
+        return None;
+    }
+    let state = match unpack_mint(mint, account) {
+        Ok(state) => state,
+        Err(e) => {
+            problems.push(e.to_string());
+            return None;
+        }
+    };
+    match state.get_extension_types() {
+        Ok(extension_types) => {
+            for extension_type in extension_types {
+                if !SUPPORTED_MINT_EXTENSIONS.contains(&extension_type) {
+                    problems.push(format!(
+                        "mint {} uses the {:?} extension, which pools do not support",
+                        mint, extension_type
+                    ));
+                }
+            }
+        }
+        Err(e) => problems.push(format!("mint {}: {}", mint, e)),
+    }
+    Some((account.owner, state.base.decimals))
+}
+
+/// Checks the parameters of a new pool against the fetched `accounts`, which must
+/// contain every existing account among both mints, the AMM config and the pool.
+///
+/// Every problem found is reported in a single `ClientError::Validation`.
+pub fn check_create_pool(
+    program_id: &Pubkey,
+    config_index: u16,
+    price: &str,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    accounts: &HashMap<Pubkey, Account>,
+) -> Result<CreatePoolParams> {
+    let mut problems = Vec::new();
+
+    let (mint0, mint1) = sort_mints(mint_a, mint_b);
+    if mint0 == mint1 {
+        problems.push(format!("both mints are {}", mint0));
+    }
+    let inverted = price_inverted(&mint0, &mint1, Some(&mint_b))?;
+    let mint0_info = check_pool_mint(&mint0, accounts.get(&mint0), &mut problems);
+    let mint1_info = check_pool_mint(&mint1, accounts.get(&mint1), &mut problems);
+
+    let amm_config = get_amm_config_key(config_index, program_id);
+    match accounts.get(&amm_config) {
+        None => problems.push(format!(
+            "AMM config {} for index {} does not exist",
+            amm_config, config_index
+        )),
+        Some(account) => {
+            if account.owner != *program_id
+                || deserialize_anchor_account::<AmmConfig>(account).is_err()
+            {
+                problems.push(format!("{} is not an AMM config", amm_config));
+            }
+        }
+    }
+
+    let pool = get_pool_key(&amm_config, &mint0, &mint1, program_id);
+    if accounts.contains_key(&pool) {
+        problems.push(format!("pool {} already exists", pool));
+    }
+
+    let mut sqrt_price_x64 = 0;
+    let mut tick = 0;
+    if let Err(e) = check_price(price) {
+        problems.push(e.to_string());
+    } else if let (Some((_, decimals_0)), Some((_, decimals_1))) = (mint0_info, mint1_info) {
+        sqrt_price_x64 =
+            quoted_price_to_sqrt_price_x64(price, decimals_0, decimals_1, inverted).unwrap_or(0);
+        if sqrt_price_x64 < tick_math::MIN_SQRT_PRICE_X64
+            || sqrt_price_x64 >= tick_math::MAX_SQRT_PRICE_X64
+        {
+            problems.push(format!(
+                "price {} is outside the range supported by the program",
+                price
+            ));
+        } else {
+            tick = tick_math::get_tick_at_sqrt_price(sqrt_price_x64)?;
+        }
+    }
+
+    match (mint0_info, mint1_info) {
+        (Some((token_program_0, decimals_0)), Some((token_program_1, decimals_1)))
+            if problems.is_empty() =>
+        {
+            Ok(CreatePoolParams {
+                mint0,
+                mint1,
+                token_program_0,
+                token_program_1,
+                decimals_0,
+                decimals_1,
+                amm_config,
+                pool,
+                price: sqrt_price_x64_to_price(sqrt_price_x64, decimals_0, decimals_1),
+                sqrt_price_x64,
+                tick,
+            })
+        }
+        _ => Err(ClientError::Validation(problems)),
+    }
+}
+
+/// Fetches the accounts a new pool depends on and checks them with [`check_create_pool`].
+pub fn validate_create_pool(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    config_index: u16,
+    price: &str,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+) -> Result<CreatePoolParams> {
+    let (mint0, mint1) = sort_mints(mint_a, mint_b);
+    let amm_config = get_amm_config_key(config_index, program_id);
+    let pool = get_pool_key(&amm_config, &mint0, &mint1, program_id);
+    let keys = [mint0, mint1, amm_config, pool];
+    let accounts = keys
+        .iter()
+        .zip(rpc_client.get_multiple_accounts(&keys)?)
+        .filter_map(|(key, account)| account.map(|account| (*key, account)))
+        .collect();
+    check_create_pool(program_id, config_index, price, mint_a, mint_b, &accounts)
+}
+
 pub fn create_pool(
     config: &Config,
     config_index: u16,
-    price: f64,
+    price: &str,
     mint0: Pubkey,
     mint1: Pubkey,
     open_time: u64,
 ) -> Result<Pubkey> {
-    let payer = read_keypair_file(&config.global.payer_path).unwrap();
-    let rpc_client = RpcClient::new(config.global.http_url.to_string());
+    let payer = read_keypair_file(&config.global.payer_path)?;
+    let rpc_client = config.rpc_client()?;
 
-    let raydium_v3_program = config.global.raydium_v3_program.parse().unwrap();
+    let raydium_v3_program = config.raydium_v3_program_id()?;
 
-    let mut price = price;
-    let mut mint0 = mint0;
-    let mut mint1 = mint1;
-    if mint0 > mint1 {
-        std::mem::swap(&mut mint0, &mut mint1);
-        price = 1.0 / price;
-    }
-    let load_pubkeys = vec![mint0, mint1];
-    let rsps = rpc_client.get_multiple_accounts(&load_pubkeys)?;
-    let mint0_owner = rsps[0].clone().unwrap().owner;
-    let mint1_owner = rsps[1].clone().unwrap().owner;
-    let mint0_account =
-        spl_token_2022::state::Mint::unpack(&rsps[0].as_ref().unwrap().data).unwrap();
-    let mint1_account =
-        spl_token_2022::state::Mint::unpack(&rsps[1].as_ref().unwrap().data).unwrap();
-
-    let sqrt_price_x64 =
-        price_to_sqrt_price_x64(price, mint0_account.decimals, mint1_account.decimals);
-
-    let (amm_config_key, __bump) = Pubkey::find_program_address(
-        &[
-            raydium_amm_v3::states::AMM_CONFIG_SEED.as_bytes(),
-            &config_index.to_be_bytes(),
-        ],
+    let params = validate_create_pool(
+        &rpc_client,
         &raydium_v3_program,
-    );
-    let tick = tick_math::get_tick_at_sqrt_price(sqrt_price_x64).unwrap();
+        config_index,
+        price,
+        mint0,
+        mint1,
+    )?;
     println!(
         "tick:{}, price:{}, sqrt_price_x64:{}, amm_config_key:{}",
-        tick, price, sqrt_price_x64, amm_config_key
+        params.tick, params.price, params.sqrt_price_x64, params.amm_config
     );
 
     let create_pool_instr = create_pool_instr(
         &config,
         &payer,
-        amm_config_key,
-        mint0,
-        mint1,
-        mint0_owner,
-        mint1_owner,
-        sqrt_price_x64,
+        params.amm_config,
+        params.mint0,
+        params.mint1,
+        params.token_program_0,
+        params.token_program_1,
+        params.sqrt_price_x64,
         open_time,
     )?;
 
     // send
     let signers = vec![&payer];
-    let recent_hash = rpc_client.get_latest_blockhash()?;
-    let txn = Transaction::new_signed_with_payer(
-        &create_pool_instr,
-        Some(&payer.pubkey()),
-        &signers,
-        recent_hash,
-    );
-    let signature = send_txn(&rpc_client, &txn, true)?;
-    println!("{}", signature);
+    let result = send_txn(config, &rpc_client, &create_pool_instr, &payer, &signers)?;
+    println!("{}", result);
 
-    let (pool, _) = Pubkey::find_program_address(
-        &[
-            POOL_SEED.as_bytes(),
-            amm_config_key.to_bytes().as_ref(),
-            mint0.to_bytes().as_ref(),
-            mint1.to_bytes().as_ref(),
-        ],
-        &raydium_v3_program,
-    );
-
-    Ok(pool)
+    Ok(params.pool)
 }
 
 pub fn create_pool_instr(
@@ -116,33 +259,11 @@ pub fn create_pool_instr(
     let url = Cluster::Custom(config.global.http_url.clone(), config.global.ws_url.clone());
     let client = Client::new(url, payer);
 
-    let program = client.program(config.global.raydium_v3_program.parse().unwrap())?;
+    let program = client.program(config.raydium_v3_program_id()?)?;
 
-    let (pool_account_key, __bump) = Pubkey::find_program_address(
-        &[
-            POOL_SEED.as_bytes(),
-            amm_config.to_bytes().as_ref(),
-            token_mint_0.to_bytes().as_ref(),
-            token_mint_1.to_bytes().as_ref(),
-        ],
-        &program.id(),
-    );
-    let (token_vault_0, __bump) = Pubkey::find_program_address(
-        &[
-            POOL_VAULT_SEED.as_bytes(),
-            pool_account_key.to_bytes().as_ref(),
-            token_mint_0.to_bytes().as_ref(),
-        ],
-        &program.id(),
-    );
-    let (token_vault_1, __bump) = Pubkey::find_program_address(
-        &[
-            POOL_VAULT_SEED.as_bytes(),
-            pool_account_key.to_bytes().as_ref(),
-            token_mint_1.to_bytes().as_ref(),
-        ],
-        &program.id(),
-    );
+    let pool_account_key = get_pool_key(&amm_config, &token_mint_0, &token_mint_1, &program.id());
+    let token_vault_0 = get_pool_vault_key(&pool_account_key, &token_mint_0, &program.id());
+    let token_vault_1 = get_pool_vault_key(&pool_account_key, &token_mint_1, &program.id());
     let (observation_key, __bump) = Pubkey::find_program_address(
         &[
             OBSERVATION_SEED.as_bytes(),
@@ -155,7 +276,7 @@ pub fn create_pool_instr(
         &amm_config,
         &token_mint_0,
         &token_mint_1,
-        &config.global.raydium_v3_program.parse().unwrap(),
+        &config.raydium_v3_program_id()?,
     );
 
     let instructions = program
@@ -1,33 +1,33 @@
 use anchor_client::{Client, Cluster};
-use anyhow::Result;
 use solana_client::rpc_client::RpcClient;
-use solana_sdk::{
-    instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer,
-    transaction::Transaction,
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
 };
-use spl_associated_token_account::get_associated_token_address_with_program_id;
 
-use crate::{config::Config, rpc::send_txn, utils::read_keypair_file};
+use crate::{
+    config::Config,
+    error::Result,
+    rpc::send_txn,
+    utils::{get_mint_token_program, read_keypair_file},
+};
 
+/// Creates the payer's associated token account for `mint`, under the token program
+/// owning the mint.
 pub fn create_token_account(config: &Config, mint: &Pubkey) -> Result<Pubkey> {
-    let payer = read_keypair_file(&config.global.payer_path).unwrap();
-    let rpc_client = RpcClient::new(config.global.http_url.to_string());
+    let payer = read_keypair_file(&config.global.payer_path)?;
+    let rpc_client = config.rpc_client()?;
 
-    let create_ata_instr = create_ata_token_account_instr(&config, &payer, mint, &payer.pubkey())?;
+    let token_program = get_mint_token_program(&rpc_client, mint)?;
+    let create_ata_instr =
+        create_ata_token_account_instr(&config, &payer, mint, &payer.pubkey(), &token_program)?;
 
     let signers = vec![&payer];
-    let recent_hash = rpc_client.get_latest_blockhash()?;
-    let txn = Transaction::new_signed_with_payer(
-        &create_ata_instr,
-        Some(&payer.pubkey()),
-        &signers,
-        recent_hash,
-    );
-
-    let _ = send_txn(&rpc_client, &txn, true)?;
+    let _ = send_txn(config, &rpc_client, &create_ata_instr, &payer, &signers)?;
 
     let token_account =
-        get_associated_token_address_with_program_id(&payer.pubkey(), &mint, &spl_token_2022::id());
+        get_associated_token_address_with_program_id(&payer.pubkey(), &mint, &token_program);
 
     Ok(token_account)
 }
@@ -37,11 +37,12 @@ pub fn create_ata_token_account_instr(
     payer: &Keypair,
     mint: &Pubkey,
     owner: &Pubkey,
+    token_program: &Pubkey,
 ) -> Result<Vec<Instruction>> {
     let url = Cluster::Custom(config.global.http_url.clone(), config.global.ws_url.clone());
     let client = Client::new(url, payer);
 
-    let program = client.program(spl_token_2022::id())?;
+    let program = client.program(*token_program)?;
     let instructions = program
         .request()
         .instruction(
@@ -49,9 +50,41 @@ pub fn create_ata_token_account_instr(
                 &program.payer(),
                 owner,
                 mint,
-                &spl_token_2022::id(),
+                token_program,
             ),
         )
         .instructions()?;
     Ok(instructions)
 }
+
+/// Returns idempotent instructions creating the associated token accounts of `owner`
+/// for the `(mint, token_program)` pairs of `mints` which do not exist yet.
+pub fn create_missing_ata_instrs(
+    rpc_client: &RpcClient,
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mints: &[(Pubkey, Pubkey)],
+) -> Result<Vec<Instruction>> {
+    let mut mints = mints.to_vec();
+    mints.sort();
+    mints.dedup();
+    if mints.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let atas: Vec<Pubkey> = mints
+        .iter()
+        .map(|(mint, token_program)| {
+            get_associated_token_address_with_program_id(owner, mint, token_program)
+        })
+        .collect();
+    let rsps = rpc_client.get_multiple_accounts(&atas)?;
+    Ok(mints
+        .iter()
+        .zip(rsps)
+        .filter(|(_, rsp)| rsp.is_none())
+        .map(|((mint, token_program), _)| {
+            create_associated_token_account_idempotent(payer, owner, mint, token_program)
+        })
+        .collect())
+}
@@ -1,138 +1,197 @@
 use anchor_client::{Client, Cluster};
 use anchor_lang::prelude::AccountMeta;
-use anyhow::Result;
 use raydium_amm_v3::accounts as raydium_accounts;
 use raydium_amm_v3::instruction as raydium_instruction;
 use raydium_amm_v3::libraries::liquidity_math;
 use raydium_amm_v3::libraries::tick_math;
+use raydium_amm_v3::states::PoolState;
 use raydium_amm_v3::states::POSITION_SEED;
 use raydium_amm_v3::states::TICK_ARRAY_SEED;
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::system_program;
-use solana_sdk::{
-    instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer,
-    transaction::Transaction,
-};
-use spl_associated_token_account::get_associated_token_address;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
 
+use crate::create_token_account::create_missing_ata_instrs;
 use crate::utils::amount_with_slippage;
-use crate::utils::deserialize_anchor_account;
-use crate::utils::get_all_nft_and_position_by_owner;
+use crate::utils::find_position;
+use crate::utils::get_anchor_account;
+use crate::utils::get_mint_token_programs;
 use crate::utils::get_pool_mints_transfer_fee;
 use crate::utils::get_tick_array_bitmap;
-use crate::utils::tick_with_spacing;
+use crate::wsol::{is_native_mint, user_token_account, WsolAccount};
 use crate::{
     config::Config,
+    error::{ClientError, Result},
     rpc::send_txn,
-    utils::{price_to_sqrt_price_x64, read_keypair_file},
+    utils::{read_keypair_file, PositionSelector},
 };
 
-pub fn decrease_liquidity(
+/// Basis points in the whole liquidity of a position.
+pub const MAX_BASIS_POINTS: u16 = 10_000;
+
+/// Liquidity withdrawn when decreasing liquidity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WithdrawAmount {
+    /// All the liquidity of the position.
+    All,
+    /// This much liquidity.
+    Liquidity(u128),
+    /// This share of the liquidity of the position, in basis points: 100% is
+    /// [`MAX_BASIS_POINTS`].
+    BasisPoints(u16),
+    /// The liquidity holding this amount of token_0, before transfer fees.
+    Token0(u64),
+    /// The liquidity holding this amount of token_1, before transfer fees.
+    Token1(u64),
+}
+
+/// What a withdrawal returns, shown before it is sent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WithdrawalPreview {
+    /// Liquidity withdrawn.
+    pub liquidity: u128,
+    /// Liquidity of the position before the withdrawal.
+    pub position_liquidity: u128,
+    /// Amounts received at the current price, after transfer fees.
+    pub amount_0_expected: u64,
+    pub amount_1_expected: u64,
+    /// Minimum amounts received after slippage and transfer fees.
+    pub amount_0_min: u64,
+    pub amount_1_min: u64,
+}
+
+impl WithdrawalPreview {
+    /// Whether the withdrawal empties the position, which is then closed.
+    pub fn closes_position(&self) -> bool {
+        self.liquidity == self.position_liquidity
+    }
+}
+
+/// Computes the liquidity to withdraw from a position holding `position_liquidity`
+/// between two sqrt prices, at `sqrt_price_x64`.
+pub fn liquidity_for_withdrawal(
+    withdraw: WithdrawAmount,
+    position_liquidity: u128,
+    sqrt_price_x64: u128,
+    sqrt_price_lower_x64: u128,
+    sqrt_price_upper_x64: u128,
+) -> Result<u128> {
+    let liquidity = match withdraw {
+        WithdrawAmount::All => position_liquidity,
+        WithdrawAmount::Liquidity(liquidity) => liquidity,
+        WithdrawAmount::BasisPoints(basis_points) => {
+            if basis_points == 0 || basis_points > MAX_BASIS_POINTS {
+                return Err(ClientError::InvalidArgument(format!(
+                    "{} basis points is not between 0 and {}",
+                    basis_points, MAX_BASIS_POINTS
+                )));
+            }
+            position_liquidity
+                .checked_mul(basis_points.into())
+                .ok_or_else(|| {
+                    ClientError::MathOverflow(format!(
+                        "{} basis points of liquidity {}",
+                        basis_points, position_liquidity
+                    ))
+                })?
+                / u128::from(MAX_BASIS_POINTS)
+        }
+        WithdrawAmount::Token0(amount_0) => liquidity_math::get_liquidity_from_single_amount_0(
+            sqrt_price_x64,
+            sqrt_price_lower_x64,
+            sqrt_price_upper_x64,
+            amount_0,
+        ),
+        WithdrawAmount::Token1(amount_1) => liquidity_math::get_liquidity_from_single_amount_1(
+            sqrt_price_x64,
+            sqrt_price_lower_x64,
+            sqrt_price_upper_x64,
+            amount_1,
+        ),
+    };
+    if liquidity == 0 {
+        return Err(ClientError::InvalidArgument(format!(
+            "{:?} withdraws no liquidity from the position",
+            withdraw
+        )));
+    }
+    if liquidity > position_liquidity {
+        return Err(ClientError::InvalidArgument(format!(
+            "{:?} needs liquidity {}, but the position holds {}",
+            withdraw, liquidity, position_liquidity
+        )));
+    }
+    Ok(liquidity)
+}
+
+/// Removes the liquidity given by `withdraw` from the position addressed by `position`,
+/// closing the position when it is emptied.
+///
+/// `confirm` receives the amounts received, at the current price and at worst after
+/// slippage and transfer fees, before anything is sent. Returning false cancels the
+/// withdrawal with `ClientError::Cancelled`.
+pub fn decrease_liquidity<F: FnOnce(&WithdrawalPreview) -> bool>(
     config: &Config,
-    tick_lower_price: f64,
-    tick_upper_price: f64,
-    liquidity: Option<u128>,
+    position: PositionSelector,
+    withdraw: WithdrawAmount,
     pool_pubkey: Pubkey,
     slippage: f64,
+    confirm: F,
 ) -> Result<()> {
-    let payer = read_keypair_file(&config.global.payer_path).unwrap();
+    let payer = read_keypair_file(&config.global.payer_path)?;
 
     let url = Cluster::Custom(config.global.http_url.clone(), config.global.ws_url.clone());
     let client = Client::new(url, &payer);
-    let rpc_client = RpcClient::new(config.global.http_url.to_string());
+    let rpc_client = config.rpc_client()?;
 
-    let program = client.program(config.global.raydium_v3_program.parse().unwrap())?;
+    let program = client.program(config.raydium_v3_program_id()?)?;
     let program_pubkey = program.id();
 
     // load pool to get observation
-    let pool: raydium_amm_v3::states::PoolState = program.account(pool_pubkey)?;
+    let pool: raydium_amm_v3::states::PoolState = get_anchor_account(&rpc_client, &pool_pubkey)?;
 
     let mint0 = pool.token_mint_0;
     let mint1 = pool.token_mint_1;
     let amm_config = pool.amm_config;
 
-    let tick_lower_price_x64 =
-        price_to_sqrt_price_x64(tick_lower_price, pool.mint_decimals_0, pool.mint_decimals_1);
-    let tick_upper_price_x64 =
-        price_to_sqrt_price_x64(tick_upper_price, pool.mint_decimals_0, pool.mint_decimals_1);
-    let tick_lower_index = tick_with_spacing(
-        tick_math::get_tick_at_sqrt_price(tick_lower_price_x64)?,
-        pool.tick_spacing.into(),
-    );
-    let tick_upper_index = tick_with_spacing(
-        tick_math::get_tick_at_sqrt_price(tick_upper_price_x64)?,
-        pool.tick_spacing.into(),
-    );
-
-    let tick_array_lower_start_index =
-        raydium_amm_v3::states::TickArrayState::get_array_start_index(
-            tick_lower_index,
-            pool.tick_spacing.into(),
-        );
-    let tick_array_upper_start_index =
-        raydium_amm_v3::states::TickArrayState::get_array_start_index(
-            tick_upper_index,
-            pool.tick_spacing.into(),
-        );
     // load position
-    let position_nft_infos =
-        get_all_nft_and_position_by_owner(&rpc_client, &payer.pubkey(), &program_pubkey);
-
-    let positions: Vec<Pubkey> = position_nft_infos
-        .iter()
-        .map(|item| item.position)
-        .collect();
-    let rsps = rpc_client.get_multiple_accounts(&positions)?;
-    let mut user_positions = Vec::new();
-    for rsp in rsps {
-        match rsp {
-            None => continue,
-            Some(rsp) => {
-                let position = deserialize_anchor_account::<
-                    raydium_amm_v3::states::PersonalPositionState,
-                >(&rsp)?;
-                user_positions.push(position);
-            }
-        }
-    }
-    let mut find_position = raydium_amm_v3::states::PersonalPositionState::default();
-    for position in user_positions {
-        if position.pool_id == pool_pubkey
-            && position.tick_lower_index == tick_lower_index
-            && position.tick_upper_index == tick_upper_index
-        {
-            find_position = position.clone();
-        }
-    }
+    let find_position = find_position(
+        &rpc_client,
+        &payer.pubkey(),
+        &program_pubkey,
+        &pool_pubkey,
+        &pool,
+        &position,
+    )?;
 
     let tickarray_bitmap_extension = get_tick_array_bitmap(
         &amm_config,
         &mint0,
         &mint1,
-        &config.global.raydium_v3_program.parse().unwrap(),
+        &config.raydium_v3_program_id()?,
     );
 
-    if find_position.nft_mint != Pubkey::default() && find_position.pool_id == pool_pubkey {
-        let user_nft_token_info = position_nft_infos
-            .iter()
-            .find(|&nft_info| nft_info.mint == find_position.nft_mint)
-            .unwrap();
-        let mut reward_vault_with_user_vault: Vec<Pubkey> = Vec::new();
-        for item in pool.reward_infos.into_iter() {
-            if item.token_mint != Pubkey::default() {
-                reward_vault_with_user_vault.push(item.token_vault);
-                reward_vault_with_user_vault.push(get_associated_token_address(
-                    &payer.pubkey(),
-                    &item.token_mint,
-                ));
-                reward_vault_with_user_vault.push(item.token_mint);
-            }
-        }
-        let liquidity = if let Some(liquidity) = liquidity {
-            liquidity
-        } else {
-            find_position.liquidity
-        };
+    if let Some((find_position, user_nft_token_info)) = find_position {
+        let tick_lower_index = find_position.tick_lower_index;
+        let tick_upper_index = find_position.tick_upper_index;
+        let tick_array_lower_start_index =
+            raydium_amm_v3::states::TickArrayState::get_array_start_index(
+                tick_lower_index,
+                pool.tick_spacing.into(),
+            );
+        let tick_array_upper_start_index =
+            raydium_amm_v3::states::TickArrayState::get_array_start_index(
+                tick_upper_index,
+                pool.tick_spacing.into(),
+            );
+        let liquidity = liquidity_for_withdrawal(
+            withdraw,
+            find_position.liquidity,
+            pool.sqrt_price_x64,
+            tick_math::get_sqrt_price_at_tick(tick_lower_index)?,
+            tick_math::get_sqrt_price_at_tick(tick_upper_index)?,
+        )?;
         let (amount_0, amount_1) = liquidity_math::get_delta_amounts_signed(
             pool.tick_current,
             pool.sqrt_price_x64,
@@ -140,30 +199,79 @@ pub fn decrease_liquidity(
             tick_upper_index,
             -(liquidity as i128),
         )?;
-        let amount_0_with_slippage = amount_with_slippage(amount_0, slippage, false);
-        let amount_1_with_slippage = amount_with_slippage(amount_1, slippage, false);
+        let amount_0_with_slippage = amount_with_slippage(amount_0, slippage, false)?;
+        let amount_1_with_slippage = amount_with_slippage(amount_1, slippage, false)?;
         let transfer_fee = get_pool_mints_transfer_fee(
             &rpc_client,
             pool.token_mint_0,
             pool.token_mint_1,
             amount_0_with_slippage,
             amount_1_with_slippage,
-        );
+        )?;
         let amount_0_min = amount_0_with_slippage
             .checked_sub(transfer_fee.0.transfer_fee)
-            .unwrap();
+            .ok_or_else(|| ClientError::MathOverflow("amount_0_min".to_string()))?;
         let amount_1_min = amount_1_with_slippage
             .checked_sub(transfer_fee.1.transfer_fee)
-            .unwrap();
+            .ok_or_else(|| ClientError::MathOverflow("amount_1_min".to_string()))?;
+
+        // preview what the withdrawal returns
+        let expected_fee = get_pool_mints_transfer_fee(
+            &rpc_client,
+            pool.token_mint_0,
+            pool.token_mint_1,
+            amount_0,
+            amount_1,
+        )?;
+        let preview = WithdrawalPreview {
+            liquidity,
+            position_liquidity: find_position.liquidity,
+            amount_0_expected: amount_0.saturating_sub(expected_fee.0.transfer_fee),
+            amount_1_expected: amount_1.saturating_sub(expected_fee.1.transfer_fee),
+            amount_0_min,
+            amount_1_min,
+        };
+        if !confirm(&preview) {
+            return Err(ClientError::Cancelled("withdrawal".to_string()));
+        }
 
         let mut remaining_accounts = Vec::new();
         remaining_accounts.push(AccountMeta::new(tickarray_bitmap_extension, false));
 
-        let mut accounts = reward_vault_with_user_vault
-            .into_iter()
-            .map(|item| AccountMeta::new(item, false))
-            .collect();
-        remaining_accounts.append(&mut accounts);
+        let reward_mints = get_reward_mints(&rpc_client, &pool)?;
+        remaining_accounts.extend(reward_remaining_accounts(
+            &pool,
+            &payer.pubkey(),
+            &reward_mints,
+        ));
+        // native SOL is received in a temporary account unwrapped at the end
+        let wsol = WsolAccount::for_mint(config, &mint0, 0)
+            .or_else(|| WsolAccount::for_mint(config, &mint1, 0));
+        let user_token_account_0 = user_token_account(
+            wsol.as_ref(),
+            &payer.pubkey(),
+            &mint0,
+            &transfer_fee.0.owner,
+        );
+        let user_token_account_1 = user_token_account(
+            wsol.as_ref(),
+            &payer.pubkey(),
+            &mint1,
+            &transfer_fee.1.owner,
+        );
+
+        // create the token accounts receiving the tokens and rewards if needed
+        let mut ata_mints: Vec<(Pubkey, Pubkey)> =
+            [(mint0, transfer_fee.0.owner), (mint1, transfer_fee.1.owner)]
+                .into_iter()
+                .filter(|(mint, _)| wsol.is_none() || !is_native_mint(mint))
+                .collect();
+        ata_mints.extend(reward_mints);
+        let mut instructions =
+            create_missing_ata_instrs(&rpc_client, &payer.pubkey(), &payer.pubkey(), &ata_mints)?;
+        if let Some(wsol) = &wsol {
+            instructions.extend(wsol.wrap_instrs(&rpc_client, &payer.pubkey(), &payer.pubkey())?);
+        }
         // personal position exist
         let mut decrease_instr = decrease_liquidity_instr(
             &config,
@@ -175,16 +283,8 @@ pub fn decrease_liquidity(
             pool.token_mint_1,
             find_position.nft_mint,
             user_nft_token_info.key,
-            spl_associated_token_account::get_associated_token_address_with_program_id(
-                &payer.pubkey(),
-                &mint0,
-                &transfer_fee.0.owner,
-            ),
-            spl_associated_token_account::get_associated_token_address_with_program_id(
-                &payer.pubkey(),
-                &mint1,
-                &transfer_fee.1.owner,
-            ),
+            user_token_account_0,
+            user_token_account_1,
             remaining_accounts,
             liquidity,
             amount_0_min,
@@ -204,24 +304,65 @@ pub fn decrease_liquidity(
             )?;
             decrease_instr.extend(close_position_instr);
         }
+        instructions.extend(decrease_instr);
+        if let Some(wsol) = &wsol {
+            instructions.push(wsol.unwrap_instr(&payer.pubkey())?);
+        }
         // send
-        let signers = vec![&payer];
-        let recent_hash = rpc_client.get_latest_blockhash()?;
-        let txn = Transaction::new_signed_with_payer(
-            &decrease_instr,
-            Some(&payer.pubkey()),
-            &signers,
-            recent_hash,
-        );
-        let signature = send_txn(&rpc_client, &txn, true)?;
-        println!("{}", signature);
+        let mut signers = vec![&payer];
+        signers.extend(wsol.as_ref().map(|wsol| &wsol.keypair));
+        let result = send_txn(config, &rpc_client, &instructions, &payer, &signers)?;
+        println!("{}", result);
     } else {
-        println!("Position doesn't exist");
+        return Err(ClientError::PositionNotFound(format!(
+            "no position in pool {} for {:?}",
+            pool_pubkey, position
+        )));
     }
 
     Ok(())
 }
 
+/// Returns the mint and the token program of every initialized reward of the pool.
+pub fn get_reward_mints(rpc_client: &RpcClient, pool: &PoolState) -> Result<Vec<(Pubkey, Pubkey)>> {
+    let reward_infos = pool.reward_infos;
+    let reward_mints: Vec<Pubkey> = reward_infos
+        .iter()
+        .map(|item| item.token_mint)
+        .filter(|mint| *mint != Pubkey::default())
+        .collect();
+    let token_programs = get_mint_token_programs(rpc_client, &reward_mints)?;
+    Ok(reward_mints.into_iter().zip(token_programs).collect())
+}
+
+/// Remaining accounts the program needs to pay out the pool rewards: the vault, the
+/// owner's token account and the mint of every initialized reward. `reward_mints` is
+/// the output of [`get_reward_mints`].
+pub fn reward_remaining_accounts(
+    pool: &PoolState,
+    owner: &Pubkey,
+    reward_mints: &[(Pubkey, Pubkey)],
+) -> Vec<AccountMeta> {
+    let mut reward_vault_with_user_vault: Vec<Pubkey> = Vec::new();
+    let reward_infos = pool
+        .reward_infos
+        .into_iter()
+        .filter(|item| item.token_mint != Pubkey::default());
+    for (item, (mint, token_program)) in reward_infos.zip(reward_mints) {
+        reward_vault_with_user_vault.push(item.token_vault);
+        reward_vault_with_user_vault.push(get_associated_token_address_with_program_id(
+            owner,
+            mint,
+            token_program,
+        ));
+        reward_vault_with_user_vault.push(*mint);
+    }
+    reward_vault_with_user_vault
+        .into_iter()
+        .map(|item| AccountMeta::new(item, false))
+        .collect()
+}
+
 pub fn decrease_liquidity_instr(
     config: &Config,
     payer: &Keypair,
@@ -246,7 +387,7 @@ pub fn decrease_liquidity_instr(
     let url = Cluster::Custom(config.global.http_url.clone(), config.global.ws_url.clone());
     let client = Client::new(url, payer);
 
-    let program = client.program(config.global.raydium_v3_program.parse().unwrap())?;
+    let program = client.program(config.raydium_v3_program_id()?)?;
     let (personal_position_key, __bump) = Pubkey::find_program_address(
         &[POSITION_SEED.as_bytes(), nft_mint_key.to_bytes().as_ref()],
         &program.id(),
@@ -316,7 +457,7 @@ pub fn close_personal_position_instr(
     let url = Cluster::Custom(config.global.http_url.clone(), config.global.ws_url.clone());
     let client = Client::new(url, payer);
 
-    let program = client.program(config.global.raydium_v3_program.parse().unwrap())?;
+    let program = client.program(config.raydium_v3_program_id()?)?;
     let (personal_position_key, __bump) = Pubkey::find_program_address(
         &[POSITION_SEED.as_bytes(), nft_mint_key.to_bytes().as_ref()],
         &program.id(),
@@ -0,0 +1,68 @@
+use solana_sdk::{program_error::ProgramError, pubkey::Pubkey};
+use thiserror::Error;
+
+/// Errors returned by every public function of the client library.
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("config error: {0}")]
+    Config(String),
+
+    #[error("failed to read keypair from {path}: {reason}")]
+    Keypair { path: String, reason: String },
+
+    #[error("rpc error: {0}")]
+    Rpc(#[from] solana_client::client_error::ClientError),
+
+    #[error("program client error: {0}")]
+    Program(#[from] anchor_client::ClientError),
+
+    #[error("raydium clmm error: {0}")]
+    Clmm(#[from] anchor_lang::error::Error),
+
+    #[error("token program error: {0}")]
+    Token(#[from] ProgramError),
+
+    #[error("account {0} not found")]
+    AccountNotFound(Pubkey),
+
+    #[error("failed to deserialize {0}")]
+    Deserialization(String),
+
+    #[error("math overflow: {0}")]
+    MathOverflow(String),
+
+    #[error("position not found: {0}")]
+    PositionNotFound(String),
+
+    #[error("slippage error: {0}")]
+    Slippage(String),
+
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
+
+    #[error("invalid parameters:\n  {}", .0.join("\n  "))]
+    Validation(Vec<String>),
+
+    #[error("swap simulation failed: {0}")]
+    Swap(String),
+
+    #[error("transaction simulation failed: {0}")]
+    Simulation(String),
+
+    #[error("transaction {signature} failed: {reason}")]
+    TransactionFailed {
+        signature: solana_sdk::signature::Signature,
+        reason: String,
+    },
+
+    #[error("transaction expired {0} times without being confirmed")]
+    TransactionExpired(usize),
+
+    #[error("{0} cancelled")]
+    Cancelled(String),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
@@ -1,114 +1,209 @@
 use anchor_client::{Client, Cluster};
 use anchor_lang::prelude::AccountMeta;
-use anyhow::Result;
 use rand::rngs::OsRng;
 use raydium_amm_v3::accounts as raydium_accounts;
 use raydium_amm_v3::instruction as raydium_instruction;
 use raydium_amm_v3::libraries::liquidity_math;
 use raydium_amm_v3::libraries::tick_math;
+use raydium_amm_v3::states::PoolState;
 use raydium_amm_v3::states::POSITION_SEED;
 use raydium_amm_v3::states::TICK_ARRAY_SEED;
 use solana_client::rpc_client::RpcClient;
-use solana_sdk::compute_budget::ComputeBudgetInstruction;
-use solana_sdk::{
-    instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer,
-    transaction::Transaction,
-};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer};
 use solana_sdk::{system_program, sysvar};
 
+use crate::create_token_account::create_missing_ata_instrs;
 use crate::utils::amount_with_slippage;
-use crate::utils::deserialize_anchor_account;
-use crate::utils::get_all_nft_and_position_by_owner;
+use crate::utils::find_position;
+use crate::utils::get_anchor_account;
 use crate::utils::get_pool_mints_inverse_fee;
+use crate::utils::get_pool_mints_transfer_fee;
 use crate::utils::get_tick_array_bitmap;
-use crate::utils::tick_with_spacing;
+use crate::utils::{price_inverted, tick_range_to_quoted_prices};
+use crate::wsol::{is_native_mint, user_token_account, WsolAccount};
 use crate::{
     config::Config,
+    error::{ClientError, Result},
     rpc::send_txn,
-    utils::{price_to_sqrt_price_x64, read_keypair_file},
+    utils::{read_keypair_file, PositionSelector},
 };
 
+/// Amount deposited when increasing liquidity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DepositAmount {
+    /// Deposit this amount of token_0, and the amount of token_1 it requires.
+    Token0(u64),
+    /// Deposit this amount of token_1, and the amount of token_0 it requires.
+    Token1(u64),
+    /// Deposit the largest liquidity both maximum amounts allow.
+    Both {
+        amount_0_max: u64,
+        amount_1_max: u64,
+    },
+}
+
+impl DepositAmount {
+    /// The amounts of token_0 and token_1 given, zero for a token not given.
+    pub fn amounts(self) -> (u64, u64) {
+        match self {
+            DepositAmount::Token0(amount_0) => (amount_0, 0),
+            DepositAmount::Token1(amount_1) => (0, amount_1),
+            DepositAmount::Both {
+                amount_0_max,
+                amount_1_max,
+            } => (amount_0_max, amount_1_max),
+        }
+    }
+}
+
+/// Computes the liquidity a deposit provides between two sqrt prices at `sqrt_price_x64`,
+/// and whether token_0 is the amount limiting it, which the program must size the
+/// liquidity from. The amounts are those reaching the vaults, net of transfer fees.
+pub fn liquidity_for_deposit(
+    deposit: DepositAmount,
+    sqrt_price_x64: u128,
+    sqrt_price_lower_x64: u128,
+    sqrt_price_upper_x64: u128,
+) -> (u128, bool) {
+    let liquidity_0 = |amount_0| {
+        liquidity_math::get_liquidity_from_single_amount_0(
+            sqrt_price_x64,
+            sqrt_price_lower_x64,
+            sqrt_price_upper_x64,
+            amount_0,
+        )
+    };
+    let liquidity_1 = |amount_1| {
+        liquidity_math::get_liquidity_from_single_amount_1(
+            sqrt_price_x64,
+            sqrt_price_lower_x64,
+            sqrt_price_upper_x64,
+            amount_1,
+        )
+    };
+    match deposit {
+        DepositAmount::Token0(amount_0) => (liquidity_0(amount_0), true),
+        DepositAmount::Token1(amount_1) => (liquidity_1(amount_1), false),
+        DepositAmount::Both {
+            amount_0_max,
+            amount_1_max,
+        } => {
+            let liquidity = liquidity_math::get_liquidity_from_amounts(
+                sqrt_price_x64,
+                sqrt_price_lower_x64,
+                sqrt_price_upper_x64,
+                amount_0_max,
+                amount_1_max,
+            );
+            // below the range only token_0 is deposited, above it only token_1
+            let is_base_0 = if sqrt_price_x64 <= sqrt_price_lower_x64 {
+                true
+            } else if sqrt_price_x64 >= sqrt_price_upper_x64 {
+                false
+            } else {
+                liquidity_0(amount_0_max) <= liquidity_1(amount_1_max)
+            };
+            (liquidity, is_base_0)
+        }
+    }
+}
+
+/// Adds liquidity to the position addressed by `position`, opening a new one when a
+/// price range matches no existing position. Returns the position NFT mint.
+///
+/// The amounts of `deposit` include transfer fees. A single amount is deposited in full
+/// and the other token is bounded by `slippage`; with both amounts, they are hard limits.
+/// The prices of the range are printed in `quote_mint`, token_1 by default.
 pub fn increase_liquidity(
     config: &Config,
-    tick_lower_price: f64,
-    tick_upper_price: f64,
-    is_base_0: bool,
-    input_amount: u64,
+    position: PositionSelector,
+    deposit: DepositAmount,
     pool_pubkey: Pubkey,
     slippage: f64,
-) -> Result<()> {
-    let payer = read_keypair_file(&config.global.payer_path).unwrap();
+    quote_mint: Option<&Pubkey>,
+) -> Result<Pubkey> {
+    let payer = read_keypair_file(&config.global.payer_path)?;
 
     let url = Cluster::Custom(config.global.http_url.clone(), config.global.ws_url.clone());
     let client = Client::new(url, &payer);
-    let rpc_client = RpcClient::new(config.global.http_url.to_string());
+    let rpc_client = config.rpc_client()?;
 
-    let program = client.program(config.global.raydium_v3_program.parse().unwrap())?;
+    let program = client.program(config.raydium_v3_program_id()?)?;
     let program_pubkey = program.id();
 
     // load pool to get observation
-    let pool: raydium_amm_v3::states::PoolState = program.account(pool_pubkey)?;
+    let pool: PoolState = get_anchor_account(&rpc_client, &pool_pubkey)?;
 
     let mint0 = pool.token_mint_0;
     let mint1 = pool.token_mint_1;
     let amm_config = pool.amm_config;
+    let inverted = price_inverted(&mint0, &mint1, quote_mint)?;
 
     // load position
-    let position_nft_infos =
-        get_all_nft_and_position_by_owner(&rpc_client, &payer.pubkey(), &program_pubkey);
-    let positions: Vec<Pubkey> = position_nft_infos
-        .iter()
-        .map(|item| item.position)
-        .collect();
-
-    let rsps = rpc_client.get_multiple_accounts(&positions)?;
-    let mut user_positions = Vec::new();
+    let find_position = find_position(
+        &rpc_client,
+        &payer.pubkey(),
+        &program_pubkey,
+        &pool_pubkey,
+        &pool,
+        &position,
+    )?;
 
-    for rsp in rsps {
-        match rsp {
-            None => continue,
-            Some(rsp) => {
-                let position = deserialize_anchor_account::<
-                    raydium_amm_v3::states::PersonalPositionState,
-                >(&rsp)?;
-                user_positions.push(position);
-            }
+    let (tick_lower_index, tick_upper_index) = match (&find_position, position) {
+        (Some((position, _)), _) => (position.tick_lower_index, position.tick_upper_index),
+        (None, PositionSelector::NftMint(nft_mint)) => {
+            return Err(ClientError::PositionNotFound(format!(
+                "no position for nft {}",
+                nft_mint
+            )))
         }
-    }
-
-    let tick_lower_price_x64 =
-        price_to_sqrt_price_x64(tick_lower_price, pool.mint_decimals_0, pool.mint_decimals_1);
-    let tick_upper_price_x64 =
-        price_to_sqrt_price_x64(tick_upper_price, pool.mint_decimals_0, pool.mint_decimals_1);
-    let tick_lower_index = tick_with_spacing(
-        tick_math::get_tick_at_sqrt_price(tick_lower_price_x64)?,
-        pool.tick_spacing.into(),
-    );
-    let tick_upper_index = tick_with_spacing(
-        tick_math::get_tick_at_sqrt_price(tick_upper_price_x64)?,
-        pool.tick_spacing.into(),
-    );
+        (None, position) => position
+            .tick_indexes(&pool)?
+            .ok_or_else(|| ClientError::InvalidArgument("no tick range".to_string()))?,
+    };
+    let (price_lower, price_upper) = tick_range_to_quoted_prices(
+        tick_lower_index,
+        tick_upper_index,
+        pool.mint_decimals_0,
+        pool.mint_decimals_1,
+        inverted,
+    )?;
     println!(
-        "tick_lower_index:{}, tick_upper_index:{}",
-        tick_lower_index, tick_upper_index
+        "tick_lower_index:{}, tick_upper_index:{}, price_lower:{}, price_upper:{}",
+        tick_lower_index, tick_upper_index, price_lower, price_upper
     );
     let tick_lower_price_x64 = tick_math::get_sqrt_price_at_tick(tick_lower_index)?;
     let tick_upper_price_x64 = tick_math::get_sqrt_price_at_tick(tick_upper_index)?;
-    let liquidity = if is_base_0 {
-        liquidity_math::get_liquidity_from_single_amount_0(
-            pool.sqrt_price_x64,
-            tick_lower_price_x64,
-            tick_upper_price_x64,
-            input_amount,
-        )
-    } else {
-        liquidity_math::get_liquidity_from_single_amount_1(
-            pool.sqrt_price_x64,
-            tick_lower_price_x64,
-            tick_upper_price_x64,
-            input_amount,
-        )
+    // size the liquidity from the amounts reaching the vaults
+    let (amount_0_in, amount_1_in) = deposit.amounts();
+    let transfer_fee = get_pool_mints_transfer_fee(
+        &rpc_client,
+        pool.token_mint_0,
+        pool.token_mint_1,
+        amount_0_in,
+        amount_1_in,
+    )?;
+    let net_amount_0 = amount_0_in.saturating_sub(transfer_fee.0.transfer_fee);
+    let net_amount_1 = amount_1_in.saturating_sub(transfer_fee.1.transfer_fee);
+    let net_deposit = match deposit {
+        DepositAmount::Token0(_) => DepositAmount::Token0(net_amount_0),
+        DepositAmount::Token1(_) => DepositAmount::Token1(net_amount_1),
+        DepositAmount::Both { .. } => DepositAmount::Both {
+            amount_0_max: net_amount_0,
+            amount_1_max: net_amount_1,
+        },
     };
+    let (liquidity, is_base_0) = liquidity_for_deposit(
+        net_deposit,
+        pool.sqrt_price_x64,
+        tick_lower_price_x64,
+        tick_upper_price_x64,
+    );
+    if liquidity == 0 {
+        return Err(ClientError::InvalidArgument(
+            "the deposit provides no liquidity in this range at the current price".to_string(),
+        ));
+    }
     let (amount_0, amount_1) = liquidity_math::get_delta_amounts_signed(
         pool.tick_current,
         pool.sqrt_price_x64,
@@ -117,30 +212,30 @@ pub fn increase_liquidity(
         liquidity as i128,
     )?;
     println!(
-        "amount_0:{}, amount_1:{}, liquidity:{}",
-        amount_0, amount_1, liquidity
-    );
-    // calc with slippage
-    let amount_0_with_slippage = amount_with_slippage(amount_0 as u64, slippage, true);
-    let amount_1_with_slippage = amount_with_slippage(amount_1 as u64, slippage, true);
-    // calc with transfer_fee
-    let transfer_fee = get_pool_mints_inverse_fee(
-        &rpc_client,
-        pool.token_mint_0,
-        pool.token_mint_1,
-        amount_0_with_slippage,
-        amount_1_with_slippage,
+        "amount_0:{}, amount_1:{}, liquidity:{}, base_0:{}",
+        amount_0, amount_1, liquidity, is_base_0
     );
+
+    // the program sizes the liquidity from the base amount at the price it executes at,
+    // the other amount is bounded by its maximum
+    let (amount_0_max, amount_1_max) = match deposit {
+        DepositAmount::Both {
+            amount_0_max,
+            amount_1_max,
+        } => (amount_0_max, amount_1_max),
+        DepositAmount::Token0(amount_0_max) => (
+            amount_0_max,
+            amount_max_with_fees(&rpc_client, &pool, false, amount_1 as u64, slippage)?,
+        ),
+        DepositAmount::Token1(amount_1_max) => (
+            amount_max_with_fees(&rpc_client, &pool, true, amount_0 as u64, slippage)?,
+            amount_1_max,
+        ),
+    };
     println!(
-        "transfer_fee_0:{}, transfer_fee_1:{}",
-        transfer_fee.0.transfer_fee, transfer_fee.1.transfer_fee
+        "amount_0_max:{}, amount_1_max:{}",
+        amount_0_max, amount_1_max
     );
-    let amount_0_max = (amount_0_with_slippage as u64)
-        .checked_add(transfer_fee.0.transfer_fee)
-        .unwrap();
-    let amount_1_max = (amount_1_with_slippage as u64)
-        .checked_add(transfer_fee.1.transfer_fee)
-        .unwrap();
 
     let tick_array_lower_start_index =
         raydium_amm_v3::states::TickArrayState::get_array_start_index(
@@ -152,36 +247,46 @@ pub fn increase_liquidity(
             tick_upper_index,
             pool.tick_spacing.into(),
         );
-    let mut find_position = raydium_amm_v3::states::PersonalPositionState::default();
-    for position in user_positions {
-        if position.pool_id == pool_pubkey
-            && position.tick_lower_index == tick_lower_index
-            && position.tick_upper_index == tick_upper_index
-        {
-            find_position = position.clone();
-        }
-    }
-
     let tickarray_bitmap_extension = get_tick_array_bitmap(
         &amm_config,
         &mint0,
         &mint1,
-        &config.global.raydium_v3_program.parse().unwrap(),
+        &config.raydium_v3_program_id()?,
     );
 
-    // Create position if not exist
-    if find_position.nft_mint == Pubkey::default() {
-        // personal position not exist
-        // new nft mint
-        let nft_mint = Keypair::generate(&mut OsRng);
-        let mut remaining_accounts = Vec::new();
-        remaining_accounts.push(AccountMeta::new(tickarray_bitmap_extension, false));
+    // native SOL is wrapped into a temporary account holding the maximum amount
+    let wsol = WsolAccount::for_mint(config, &mint0, amount_0_max)
+        .or_else(|| WsolAccount::for_mint(config, &mint1, amount_1_max));
+    let user_token_account_0 = user_token_account(
+        wsol.as_ref(),
+        &payer.pubkey(),
+        &mint0,
+        &transfer_fee.0.owner,
+    );
+    let user_token_account_1 = user_token_account(
+        wsol.as_ref(),
+        &payer.pubkey(),
+        &mint1,
+        &transfer_fee.1.owner,
+    );
+
+    // create the token accounts funding the position if needed
+    let ata_mints: Vec<(Pubkey, Pubkey)> =
+        [(mint0, transfer_fee.0.owner), (mint1, transfer_fee.1.owner)]
+            .into_iter()
+            .filter(|(mint, _)| wsol.is_none() || !is_native_mint(mint))
+            .collect();
+    let mut instructions =
+        create_missing_ata_instrs(&rpc_client, &payer.pubkey(), &payer.pubkey(), &ata_mints)?;
+    if let Some(wsol) = &wsol {
+        instructions.extend(wsol.wrap_instrs(&rpc_client, &payer.pubkey(), &payer.pubkey())?);
+    }
 
-        let mut instructions = Vec::new();
-        let request_inits_instr = ComputeBudgetInstruction::set_compute_unit_limit(1400_000u32);
-        instructions.push(request_inits_instr);
+    if let Some((find_position, user_nft_token_info)) = find_position {
+        let mut remaining_accounts = Vec::new();
+        remaining_accounts.push(AccountMeta::new_readonly(tickarray_bitmap_extension, false));
 
-        let open_position_instr = open_position_with_token22_nft_instr(
+        let increase_instr = increase_liquidity_instr(
             &config,
             &payer,
             pool_pubkey,
@@ -189,50 +294,39 @@ pub fn increase_liquidity(
             pool.token_vault_1,
             pool.token_mint_0,
             pool.token_mint_1,
-            nft_mint.pubkey(),
-            payer.pubkey(),
-            spl_associated_token_account::get_associated_token_address_with_program_id(
-                &payer.pubkey(),
-                &mint0,
-                &transfer_fee.0.owner,
-            ),
-            spl_associated_token_account::get_associated_token_address_with_program_id(
-                &payer.pubkey(),
-                &mint1,
-                &transfer_fee.1.owner,
-            ),
+            find_position.nft_mint,
+            user_nft_token_info.key,
+            user_token_account_0,
+            user_token_account_1,
             remaining_accounts,
-            liquidity,
+            0,
             amount_0_max,
             amount_1_max,
             tick_lower_index,
             tick_upper_index,
             tick_array_lower_start_index,
             tick_array_upper_start_index,
-            false,
+            Some(is_base_0),
         )?;
-        instructions.extend(open_position_instr);
+        instructions.extend(increase_instr);
+        if let Some(wsol) = &wsol {
+            instructions.push(wsol.unwrap_instr(&payer.pubkey())?);
+        }
         // send
-        let signers = vec![&payer, &nft_mint];
-        let recent_hash = rpc_client.get_latest_blockhash()?;
-        let txn = Transaction::new_signed_with_payer(
-            &instructions,
-            Some(&payer.pubkey()),
-            &signers,
-            recent_hash,
-        );
-        let signature = send_txn(&rpc_client, &txn, true)?;
-        println!("Position created: {}", signature);
+        let mut signers = vec![&payer];
+        signers.extend(wsol.as_ref().map(|wsol| &wsol.keypair));
+        let result = send_txn(config, &rpc_client, &instructions, &payer, &signers)?;
+        println!("Liquidity added: {}", result);
+        Ok(find_position.nft_mint)
     } else {
-        let user_nft_token_info = position_nft_infos
-            .iter()
-            .find(|&nft_info| nft_info.mint == find_position.nft_mint)
-            .unwrap();
-
+        // Create position if not exist
+        // personal position not exist
+        // new nft mint
+        let nft_mint = Keypair::generate(&mut OsRng);
         let mut remaining_accounts = Vec::new();
-        remaining_accounts.push(AccountMeta::new_readonly(tickarray_bitmap_extension, false));
+        remaining_accounts.push(AccountMeta::new(tickarray_bitmap_extension, false));
 
-        let increase_instr = increase_liquidity_instr(
+        let open_position_instr = open_position_with_token22_nft_instr(
             &config,
             &payer,
             pool_pubkey,
@@ -240,41 +334,66 @@ pub fn increase_liquidity(
             pool.token_vault_1,
             pool.token_mint_0,
             pool.token_mint_1,
-            find_position.nft_mint,
-            user_nft_token_info.key,
-            spl_associated_token_account::get_associated_token_address_with_program_id(
-                &payer.pubkey(),
-                &mint0,
-                &transfer_fee.0.owner,
-            ),
-            spl_associated_token_account::get_associated_token_address_with_program_id(
-                &payer.pubkey(),
-                &mint1,
-                &transfer_fee.0.owner,
-            ),
+            nft_mint.pubkey(),
+            payer.pubkey(),
+            user_token_account_0,
+            user_token_account_1,
             remaining_accounts,
-            liquidity,
+            0,
             amount_0_max,
             amount_1_max,
             tick_lower_index,
             tick_upper_index,
             tick_array_lower_start_index,
             tick_array_upper_start_index,
+            false,
+            Some(is_base_0),
         )?;
+        instructions.extend(open_position_instr);
+        if let Some(wsol) = &wsol {
+            instructions.push(wsol.unwrap_instr(&payer.pubkey())?);
+        }
         // send
-        let signers = vec![&payer];
-        let recent_hash = rpc_client.get_latest_blockhash()?;
-        let txn = Transaction::new_signed_with_payer(
-            &increase_instr,
-            Some(&payer.pubkey()),
-            &signers,
-            recent_hash,
-        );
-        let signature = send_txn(&rpc_client, &txn, true)?;
-        println!("Liquidity added: {}", signature);
+        let mut signers = vec![&payer, &nft_mint];
+        signers.extend(wsol.as_ref().map(|wsol| &wsol.keypair));
+        let result = send_txn(config, &rpc_client, &instructions, &payer, &signers)?;
+        println!("Position created: {}", result);
+        Ok(nft_mint.pubkey())
     }
+}
 
-    Ok(())
+/// Returns the maximum amount of a token to pay for depositing `amount` in the pool,
+/// with `slippage` and the mint's transfer fee.
+fn amount_max_with_fees(
+    rpc_client: &RpcClient,
+    pool: &PoolState,
+    is_token_0: bool,
+    amount: u64,
+    slippage: f64,
+) -> Result<u64> {
+    let amount_with_slippage = amount_with_slippage(amount, slippage, true)?;
+    let transfer_fee = if is_token_0 {
+        get_pool_mints_inverse_fee(
+            rpc_client,
+            pool.token_mint_0,
+            pool.token_mint_1,
+            amount_with_slippage,
+            0,
+        )?
+        .0
+    } else {
+        get_pool_mints_inverse_fee(
+            rpc_client,
+            pool.token_mint_0,
+            pool.token_mint_1,
+            0,
+            amount_with_slippage,
+        )?
+        .1
+    };
+    amount_with_slippage
+        .checked_add(transfer_fee.transfer_fee)
+        .ok_or_else(|| ClientError::MathOverflow("amount_max".to_string()))
 }
 
 pub fn increase_liquidity_instr(
@@ -297,11 +416,12 @@ pub fn increase_liquidity_instr(
     tick_upper_index: i32,
     tick_array_lower_start_index: i32,
     tick_array_upper_start_index: i32,
+    base_flag: Option<bool>,
 ) -> Result<Vec<Instruction>> {
     let url = Cluster::Custom(config.global.http_url.clone(), config.global.ws_url.clone());
     let client = Client::new(url, payer);
 
-    let program = client.program(config.global.raydium_v3_program.parse().unwrap())?;
+    let program = client.program(config.raydium_v3_program_id()?)?;
     let (tick_array_lower, __bump) = Pubkey::find_program_address(
         &[
             TICK_ARRAY_SEED.as_bytes(),
@@ -356,7 +476,7 @@ pub fn increase_liquidity_instr(
             liquidity,
             amount_0_max,
             amount_1_max,
-            base_flag: None,
+            base_flag,
         })
         .instructions()?;
     Ok(instructions)
@@ -383,11 +503,12 @@ pub fn open_position_with_token22_nft_instr(
     tick_array_lower_start_index: i32,
     tick_array_upper_start_index: i32,
     with_metadata: bool,
+    base_flag: Option<bool>,
 ) -> Result<Vec<Instruction>> {
     let url = Cluster::Custom(config.global.http_url.clone(), config.global.ws_url.clone());
 
     let client = Client::new(url, payer);
-    let program = client.program(config.global.raydium_v3_program.parse().unwrap())?;
+    let program = client.program(config.raydium_v3_program_id()?)?;
     let nft_ata_token_account =
         spl_associated_token_account::get_associated_token_address_with_program_id(
             &program.payer(),
@@ -457,7 +578,7 @@ pub fn open_position_with_token22_nft_instr(
             tick_array_lower_start_index,
             tick_array_upper_start_index,
             with_metadata,
-            base_flag: None,
+            base_flag,
         })
         .instructions()?;
     Ok(instructions)
@@ -0,0 +1,196 @@
+use anchor_lang::prelude::AccountMeta;
+use rand::rngs::OsRng;
+use raydium_amm_v3::libraries::tick_math;
+use raydium_amm_v3::states::{AmmConfig, TickArrayState};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+use crate::create_pool::{create_pool_instr, validate_create_pool};
+use crate::increase_liquidity::{
+    liquidity_for_deposit, open_position_with_token22_nft_instr, DepositAmount,
+};
+use crate::utils::get_anchor_account;
+use crate::utils::get_pool_mints_transfer_fee;
+use crate::utils::get_pool_vault_key;
+use crate::utils::get_tick_array_bitmap;
+use crate::utils::price_inverted;
+use crate::utils::price_range_to_ticks;
+use crate::wsol::{user_token_account, WsolAccount};
+use crate::{
+    config::Config,
+    error::{ClientError, Result},
+    rpc::send_txn,
+    utils::read_keypair_file,
+};
+
+/// Accounts created by [`launch_pool`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct LaunchPoolResult {
+    pub pool: Pubkey,
+    pub nft_mint: Pubkey,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub liquidity: u128,
+}
+
+/// Creates a pool at `price` and opens its first position in the same transaction, so
+/// that the pool never exists without liquidity.
+///
+/// `price`, the range prices and the amounts are given in the order of `mint_a` and
+/// `mint_b`; they are converted when the pool orders the mints the other way. The
+/// amounts are the maximum deposited of each token, transfer fees included.
+#[allow(clippy::too_many_arguments)]
+pub fn launch_pool(
+    config: &Config,
+    config_index: u16,
+    price: &str,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    tick_lower_price: &str,
+    tick_upper_price: &str,
+    amount_a_max: u64,
+    amount_b_max: u64,
+    open_time: u64,
+) -> Result<LaunchPoolResult> {
+    let payer = read_keypair_file(&config.global.payer_path)?;
+    let rpc_client = config.rpc_client()?;
+    let program_id = config.raydium_v3_program_id()?;
+
+    let params = validate_create_pool(
+        &rpc_client,
+        &program_id,
+        config_index,
+        price,
+        mint_a,
+        mint_b,
+    )?;
+    let inverted = price_inverted(&params.mint0, &params.mint1, Some(&mint_b))?;
+    let (amount_0_max, amount_1_max) = if inverted {
+        (amount_b_max, amount_a_max)
+    } else {
+        (amount_a_max, amount_b_max)
+    };
+
+    let amm_config = get_anchor_account::<AmmConfig>(&rpc_client, &params.amm_config)?;
+
+    let (tick_lower_index, tick_upper_index) = price_range_to_ticks(
+        tick_lower_price,
+        tick_upper_price,
+        params.decimals_0,
+        params.decimals_1,
+        amm_config.tick_spacing,
+        inverted,
+    )?;
+    if tick_lower_index >= tick_upper_index {
+        return Err(ClientError::InvalidArgument(format!(
+            "price range {} - {} is empty at tick spacing {}",
+            tick_lower_price, tick_upper_price, amm_config.tick_spacing
+        )));
+    }
+
+    // size the liquidity from the amounts reaching the vaults
+    let transfer_fee = get_pool_mints_transfer_fee(
+        &rpc_client,
+        params.mint0,
+        params.mint1,
+        amount_0_max,
+        amount_1_max,
+    )?;
+    let (liquidity, is_base_0) = liquidity_for_deposit(
+        DepositAmount::Both {
+            amount_0_max: amount_0_max.saturating_sub(transfer_fee.0.transfer_fee),
+            amount_1_max: amount_1_max.saturating_sub(transfer_fee.1.transfer_fee),
+        },
+        params.sqrt_price_x64,
+        tick_math::get_sqrt_price_at_tick(tick_lower_index)?,
+        tick_math::get_sqrt_price_at_tick(tick_upper_index)?,
+    );
+    if liquidity == 0 {
+        return Err(ClientError::InvalidArgument(
+            "the amounts are too small to provide liquidity in this range".to_string(),
+        ));
+    }
+    println!(
+        "tick:{}, tick_lower_index:{}, tick_upper_index:{}, liquidity:{}",
+        params.tick, tick_lower_index, tick_upper_index, liquidity
+    );
+
+    // native SOL is wrapped into a temporary account holding the maximum amount
+    let wsol = WsolAccount::for_mint(config, &params.mint0, amount_0_max)
+        .or_else(|| WsolAccount::for_mint(config, &params.mint1, amount_1_max));
+    let user_token_account_0 = user_token_account(
+        wsol.as_ref(),
+        &payer.pubkey(),
+        &params.mint0,
+        &params.token_program_0,
+    );
+    let user_token_account_1 = user_token_account(
+        wsol.as_ref(),
+        &payer.pubkey(),
+        &params.mint1,
+        &params.token_program_1,
+    );
+
+    let mut instructions = Vec::new();
+    if let Some(wsol) = &wsol {
+        instructions.extend(wsol.wrap_instrs(&rpc_client, &payer.pubkey(), &payer.pubkey())?);
+    }
+    instructions.extend(create_pool_instr(
+        config,
+        &payer,
+        params.amm_config,
+        params.mint0,
+        params.mint1,
+        params.token_program_0,
+        params.token_program_1,
+        params.sqrt_price_x64,
+        open_time,
+    )?);
+
+    let nft_mint = Keypair::generate(&mut OsRng);
+    let tickarray_bitmap_extension = get_tick_array_bitmap(
+        &params.amm_config,
+        &params.mint0,
+        &params.mint1,
+        &program_id,
+    );
+    instructions.extend(open_position_with_token22_nft_instr(
+        config,
+        &payer,
+        params.pool,
+        get_pool_vault_key(&params.pool, &params.mint0, &program_id),
+        get_pool_vault_key(&params.pool, &params.mint1, &program_id),
+        params.mint0,
+        params.mint1,
+        nft_mint.pubkey(),
+        payer.pubkey(),
+        user_token_account_0,
+        user_token_account_1,
+        vec![AccountMeta::new(tickarray_bitmap_extension, false)],
+        0,
+        amount_0_max,
+        amount_1_max,
+        tick_lower_index,
+        tick_upper_index,
+        TickArrayState::get_array_start_index(tick_lower_index, amm_config.tick_spacing),
+        TickArrayState::get_array_start_index(tick_upper_index, amm_config.tick_spacing),
+        false,
+        Some(is_base_0),
+    )?);
+    if let Some(wsol) = &wsol {
+        instructions.push(wsol.unwrap_instr(&payer.pubkey())?);
+    }
+
+    // send
+    let mut signers = vec![&payer, &nft_mint];
+    signers.extend(wsol.as_ref().map(|wsol| &wsol.keypair));
+    let result = send_txn(config, &rpc_client, &instructions, &payer, &signers)?;
+    println!("Pool launched: {}", result);
+
+    Ok(LaunchPoolResult {
+        pool: params.pool,
+        nft_mint: nft_mint.pubkey(),
+        tick_lower_index,
+        tick_upper_index,
+        liquidity,
+    })
+}
@@ -1,17 +1,35 @@
+pub mod collect;
 pub mod config;
 pub mod create_mint;
 pub mod create_pool;
 pub mod create_token_account;
 pub mod decrease_liquidity;
+pub mod error;
 pub mod increase_liquidity;
+pub mod launch_pool;
 pub mod mint_to;
+pub mod pool;
+pub mod positions;
+pub mod price;
+pub mod quote;
+pub mod rebalance;
 pub mod rpc;
+pub mod swap;
+pub mod wsol;
 
 pub mod utils;
 
+pub use collect::*;
 pub use create_mint::*;
 pub use create_pool::*;
 pub use create_token_account::*;
 pub use decrease_liquidity::*;
+pub use error::ClientError;
 pub use increase_liquidity::*;
+pub use launch_pool::*;
 pub use mint_to::*;
+pub use pool::*;
+pub use positions::*;
+pub use quote::*;
+pub use rebalance::*;
+pub use swap::*;
@@ -1,10 +1,23 @@
-use std::str::FromStr;
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    process,
+    str::FromStr,
+};
 
-use client::{self, config::Config};
+use client::{
+    self,
+    config::{Config, GlobalOverrides},
+    utils::{
+        price_inverted, quote_liquidity_buckets, quote_pool, quote_position,
+        tick_range_to_quoted_prices, PositionSelector, TickRange,
+    },
+    ClientError, DepositAmount, MintExtension, MintOptions, RebalanceResult, WithdrawAmount,
+    WithdrawalPreview,
+};
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::{program_pack::Pack, pubkey::Pubkey};
 
 /// Top-level struct for parsing command-line arguments.
@@ -19,6 +32,51 @@ pub struct Args {
     #[arg(short, long, default_value = "config.toml")]
     pub config: String,
 
+    /// Named profile from the configuration file (e.g. `devnet` or `mainnet`)
+    /// layered over the `[global]` section.
+    #[arg(short, long)]
+    pub profile: Option<String>,
+
+    /// Override the Solana RPC URL (also settable with `RAYDIUM_HTTP_URL`).
+    #[arg(long)]
+    pub http_url: Option<String>,
+
+    /// Override the Solana Websocket URL (also settable with `RAYDIUM_WS_URL`).
+    #[arg(long)]
+    pub ws_url: Option<String>,
+
+    /// Override the payer keypair path (also settable with `RAYDIUM_PAYER_PATH`).
+    #[arg(long)]
+    pub payer_path: Option<String>,
+
+    /// Override the Raydium V3 program ID (also settable with `RAYDIUM_V3_PROGRAM`).
+    #[arg(long)]
+    pub raydium_v3_program: Option<String>,
+
+    /// Override the commitment transactions are confirmed at (also settable with
+    /// `RAYDIUM_COMMITMENT`).
+    #[arg(long)]
+    pub commitment: Option<String>,
+
+    /// Simulate every transaction instead of sending it, printing compute units,
+    /// logs, balance changes and decoded errors.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Use the payer's persistent WSOL token account for pools paired with SOL instead of
+    /// wrapping and unwrapping native SOL in a temporary account.
+    #[arg(long)]
+    pub no_wrap_sol: bool,
+
+    /// Override the `priority_fee.compute_unit_price` (micro-lamports) for this command.
+    #[arg(long)]
+    pub compute_unit_price: Option<u64>,
+
+    /// Override the `priority_fee.compute_unit_limit` for this command instead of
+    /// deriving it from a simulation.
+    #[arg(long)]
+    pub compute_unit_limit: Option<u32>,
+
     /// Choose a subcommand to execute. The subcommands are grouped into different
     /// categories such as `raydium` for Raydium-related commands and `solend` for Solend-related commands.
     #[command(subcommand)]
@@ -39,6 +97,24 @@ pub enum Subcommands {
     SolendSubcommands(SolendSubcommands),
 }
 
+/// Token program a new mint is created with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum TokenProgram {
+    /// The original SPL Token program.
+    Spl,
+    /// The Token-2022 program, required for extensions.
+    Token2022,
+}
+
+impl TokenProgram {
+    fn id(self) -> Pubkey {
+        match self {
+            TokenProgram::Spl => spl_token::id(),
+            TokenProgram::Token2022 => spl_token_2022::id(),
+        }
+    }
+}
+
 /// Subcommands under the Raydium category.
 ///
 /// Each variant represents a specific operation. The `--help` flag will
@@ -46,7 +122,38 @@ pub enum Subcommands {
 #[derive(Debug, Subcommand)]
 pub enum RaydiumSubcommands {
     /// Mint a new token.
-    MintToken,
+    MintToken {
+        /// Number of decimals of the token.
+        #[arg(long, default_value_t = 0)]
+        decimals: u8,
+        /// Mint authority, the payer by default. Also the authority of the extensions.
+        #[arg(long)]
+        mint_authority: Option<Pubkey>,
+        /// Freeze authority. The mint has none if omitted.
+        #[arg(long)]
+        freeze_authority: Option<Pubkey>,
+        /// Token program owning the mint.
+        #[arg(long, value_enum, default_value_t = TokenProgram::Token2022)]
+        token_program: TokenProgram,
+        /// Token-2022 transfer fee, in basis points of the transferred amount.
+        #[arg(long, requires = "max_transfer_fee")]
+        transfer_fee_bps: Option<u16>,
+        /// Maximum Token-2022 transfer fee, in base units.
+        #[arg(long, requires = "transfer_fee_bps")]
+        max_transfer_fee: Option<u64>,
+        /// Token-2022 interest rate, in basis points per year.
+        #[arg(long, allow_hyphen_values = true)]
+        interest_rate: Option<i16>,
+        /// Add a Token-2022 metadata pointer.
+        #[arg(long)]
+        metadata_pointer: bool,
+        /// Account the metadata pointer points to, the mint itself by default.
+        #[arg(long, requires = "metadata_pointer")]
+        metadata_address: Option<Pubkey>,
+        /// Make the token non-transferable (Token-2022 only).
+        #[arg(long)]
+        non_transferable: bool,
+    },
 
     /// Create a token account for the specified mint.
     CreateTokenAccount {
@@ -64,48 +171,336 @@ pub enum RaydiumSubcommands {
         amount: u64,
     },
 
-    /// Increase liquidity in a pool by specifying the price range and input amount.
+    /// Increase liquidity in a pool, either in an existing position selected by its NFT
+    /// or in the position matching a price range, which is opened if it does not exist.
     IncreaseLiquidity {
-        /// Lower bound of the tick price.
-        tick_lower_price: f64,
-        /// Upper bound of the tick price.
-        tick_upper_price: f64,
-        /// Input amount used for liquidity.
+        /// Amount of the base token to deposit, transfer fees included.
         input_amount: u64,
         /// The public key of the liquidity pool.
         pool_pubkey: Pubkey,
         /// Allowed slippage when adding liquidity.
         slippage: f64,
+        /// Mint of the NFT of the position to add liquidity to.
+        #[arg(long, conflicts_with_all = [
+            "tick_lower_price", "tick_upper_price", "tick_lower", "tick_upper",
+            "range_percent", "range_spacings", "full_range",
+        ])]
+        position_nft: Option<Pubkey>,
+        /// Lower bound of the tick price.
+        #[arg(long, required_unless_present_any = [
+            "position_nft", "tick_lower", "range_percent", "range_spacings", "full_range",
+        ])]
+        tick_lower_price: Option<String>,
+        /// Upper bound of the tick price.
+        #[arg(long, required_unless_present_any = [
+            "position_nft", "tick_lower", "range_percent", "range_spacings", "full_range",
+        ])]
+        tick_upper_price: Option<String>,
+        /// Lower tick index of the range, instead of a price.
+        #[arg(long, allow_negative_numbers = true, requires = "tick_upper", conflicts_with_all = [
+            "tick_lower_price", "tick_upper_price", "range_percent", "range_spacings", "full_range",
+        ])]
+        tick_lower: Option<i32>,
+        /// Upper tick index of the range, instead of a price.
+        #[arg(long, allow_negative_numbers = true, requires = "tick_lower")]
+        tick_upper: Option<i32>,
+        /// Range from this percentage below to this percentage above the current price.
+        #[arg(long, conflicts_with_all = [
+            "tick_lower_price", "tick_upper_price", "range_spacings", "full_range",
+        ])]
+        range_percent: Option<f64>,
+        /// Range of this many tick spacings either side of the current one.
+        #[arg(long, conflicts_with_all = ["tick_lower_price", "tick_upper_price", "full_range"])]
+        range_spacings: Option<u32>,
+        /// Range covering every price the pool's tick spacing allows.
+        #[arg(long, conflicts_with_all = ["tick_lower_price", "tick_upper_price"])]
+        full_range: bool,
+        /// Mint the prices are given and printed in, token_1 of the pool by default.
+        #[arg(long)]
+        quote_in: Option<Pubkey>,
+        /// Token `input_amount` is an amount of.
+        #[arg(long, value_enum, default_value_t = DepositBase::Token0)]
+        base: DepositBase,
+        /// Maximum amount of the other token. When set, `input_amount` is a maximum too
+        /// and the largest liquidity both amounts allow is deposited.
+        #[arg(long)]
+        other_amount_max: Option<u64>,
     },
 
-    /// Decrease liquidity from a pool by specifying the price range and liquidity.
+    /// Decrease liquidity from a position selected by its NFT or by its price range.
     DecreaseLiquidity {
-        /// Lower bound of the tick price.
-        tick_lower_price: f64,
-        /// Upper bound of the tick price.
-        tick_upper_price: f64,
         /// The public key of the liquidity pool.
         pool_pubkey: Pubkey,
         /// Allowed slippage when removing liquidity.
         slippage: f64,
         /// Optional liquidity parameter to remove. If not provided, all liquidity is removed.
+        #[arg(conflicts_with_all = ["percent", "amount0", "amount1"])]
         liquidity: Option<u128>,
+        /// Percentage of the position's liquidity to remove, from 0 to 100 with at most
+        /// two decimals.
+        #[arg(long, value_parser = parse_basis_points, conflicts_with_all = ["amount0", "amount1"])]
+        percent: Option<u16>,
+        /// Remove the liquidity holding this amount of token_0.
+        #[arg(long, conflicts_with = "amount1")]
+        amount0: Option<u64>,
+        /// Remove the liquidity holding this amount of token_1.
+        #[arg(long)]
+        amount1: Option<u64>,
+        /// Mint of the NFT of the position to remove liquidity from.
+        #[arg(long, conflicts_with_all = ["tick_lower_price", "tick_upper_price"])]
+        position_nft: Option<Pubkey>,
+        /// Lower bound of the tick price.
+        #[arg(long, required_unless_present = "position_nft")]
+        tick_lower_price: Option<String>,
+        /// Upper bound of the tick price.
+        #[arg(long, required_unless_present = "position_nft")]
+        tick_upper_price: Option<String>,
+        /// Mint the prices are quoted in, token_1 of the pool by default.
+        #[arg(long)]
+        quote_in: Option<Pubkey>,
+        /// Send the withdrawal without asking for confirmation after the preview.
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Move a position to a new range: withdraw all its liquidity and close it,
+    /// optionally swap the proceeds to the ratio of the new range, and open a new position.
+    Rebalance {
+        /// The public key of the liquidity pool.
+        pool_pubkey: Pubkey,
+        /// Allowed slippage when withdrawing, swapping and depositing.
+        slippage: f64,
+        /// Mint of the NFT of the position to move.
+        #[arg(long)]
+        position_nft: Pubkey,
+        /// Lower price of the new range.
+        #[arg(long, required_unless_present_any = [
+            "tick_lower", "range_percent", "range_spacings", "full_range",
+        ])]
+        tick_lower_price: Option<String>,
+        /// Upper price of the new range.
+        #[arg(long, required_unless_present_any = [
+            "tick_lower", "range_percent", "range_spacings", "full_range",
+        ])]
+        tick_upper_price: Option<String>,
+        /// Lower tick index of the new range, instead of a price.
+        #[arg(long, allow_negative_numbers = true, requires = "tick_upper", conflicts_with_all = [
+            "tick_lower_price", "tick_upper_price", "range_percent", "range_spacings", "full_range",
+        ])]
+        tick_lower: Option<i32>,
+        /// Upper tick index of the new range, instead of a price.
+        #[arg(long, allow_negative_numbers = true, requires = "tick_lower")]
+        tick_upper: Option<i32>,
+        /// New range from this percentage below to this percentage above the current price.
+        #[arg(long, conflicts_with_all = [
+            "tick_lower_price", "tick_upper_price", "range_spacings", "full_range",
+        ])]
+        range_percent: Option<f64>,
+        /// New range of this many tick spacings either side of the current one.
+        #[arg(long, conflicts_with_all = ["tick_lower_price", "tick_upper_price", "full_range"])]
+        range_spacings: Option<u32>,
+        /// New range covering every price the pool's tick spacing allows.
+        #[arg(long, conflicts_with_all = ["tick_lower_price", "tick_upper_price"])]
+        full_range: bool,
+        /// Mint the prices are given and printed in, token_1 of the pool by default.
+        #[arg(long)]
+        quote_in: Option<Pubkey>,
+        /// Swap the withdrawn tokens to the ratio the new range needs before depositing.
+        #[arg(long)]
+        swap: bool,
     },
 
     /// Create a new pool using the provided parameters.
     CreatePool {
         /// Configuration index for the pool.
         config_index: u16,
-        /// Initial price for the pool.
-        price: f64,
+        /// Initial price for the pool, in units of mint1 per mint0.
+        price: String,
+        /// The public key of the first token's mint.
+        mint0: Pubkey,
+        /// The public key of the second token's mint.
+        mint1: Pubkey,
+        /// Open time for the pool (optional, defaults to 0).
+        #[arg(short, long, default_value_t = 0)]
+        open_time: u64,
+        /// Mint the price is quoted in, mint1 by default.
+        #[arg(long)]
+        quote_in: Option<Pubkey>,
+    },
+
+    /// Create a new pool and open its first position in a single transaction
+    LaunchPool {
+        /// Configuration index for the pool.
+        config_index: u16,
+        /// Initial price for the pool, in units of mint1 per mint0.
+        price: String,
         /// The public key of the first token's mint.
         mint0: Pubkey,
         /// The public key of the second token's mint.
         mint1: Pubkey,
+        /// Lower price of the position's range.
+        #[arg(long)]
+        tick_lower_price: String,
+        /// Upper price of the position's range.
+        #[arg(long)]
+        tick_upper_price: String,
+        /// Maximum amount of mint0 to deposit.
+        #[arg(long)]
+        amount0: u64,
+        /// Maximum amount of mint1 to deposit.
+        #[arg(long)]
+        amount1: u64,
         /// Open time for the pool (optional, defaults to 0).
         #[arg(short, long, default_value_t = 0)]
         open_time: u64,
+        /// Mint the prices are quoted in, mint1 by default.
+        #[arg(long)]
+        quote_in: Option<Pubkey>,
+    },
+
+    /// Swap tokens in a pool, either with an exact input or an exact output amount.
+    Swap {
+        /// The public key of the liquidity pool.
+        pool_pubkey: Pubkey,
+        /// The mint of the token being sold.
+        input_mint: Pubkey,
+        /// Input amount, or output amount when `--exact-out` is set.
+        amount: u64,
+        /// Allowed slippage for the swap.
+        slippage: f64,
+        /// Treat the amount as the exact output instead of the exact input.
+        #[arg(long)]
+        exact_out: bool,
+        /// Optional price limit (token_1 per token_0) at which the swap stops.
+        #[arg(long)]
+        limit_price: Option<String>,
+        /// Mint the limit price is quoted in, token_1 of the pool by default.
+        #[arg(long, requires = "limit_price")]
+        quote_in: Option<Pubkey>,
+    },
+
+    /// Quote a swap by simulating it locally against the pool's tick arrays.
+    Quote {
+        /// The public key of the liquidity pool.
+        pool_pubkey: Pubkey,
+        /// The mint of the token being sold.
+        input_mint: Pubkey,
+        /// Input amount, or output amount when `--exact-out` is set.
+        amount: u64,
+        /// Treat the amount as the exact output instead of the exact input.
+        #[arg(long)]
+        exact_out: bool,
+        /// Optional price limit (token_1 per token_0) at which the swap stops.
+        #[arg(long)]
+        limit_price: Option<String>,
+        /// Mint the limit price and the printed prices are quoted in, token_1 of the pool
+        /// by default.
+        #[arg(long)]
+        quote_in: Option<Pubkey>,
+        /// Load the pool accounts from this directory instead of the RPC node.
+        #[arg(long)]
+        accounts_dir: Option<PathBuf>,
+        /// Save the loaded pool accounts to this directory for later offline quotes.
+        #[arg(long)]
+        save_accounts: Option<PathBuf>,
+    },
+
+    /// Collect the trading fees and rewards of positions without removing liquidity.
+    Collect {
+        /// The public key of the liquidity pool.
+        pool_pubkey: Pubkey,
+        /// Mint of the NFT of the position to collect from.
+        #[arg(long, required_unless_present = "all", conflicts_with = "all")]
+        position_nft: Option<Pubkey>,
+        /// Collect from every position the payer holds in the pool.
+        #[arg(long)]
+        all: bool,
     },
+
+    /// Inspect the CLMM positions owned by the payer.
+    #[command(subcommand)]
+    Positions(PositionsSubcommands),
+
+    /// Inspect CLMM pools.
+    #[command(subcommand)]
+    Pool(PoolSubcommands),
+}
+
+/// Subcommands under `raydium positions`.
+#[derive(Debug, Subcommand)]
+pub enum PositionsSubcommands {
+    /// List every position with its range, amounts and uncollected fees and rewards.
+    List {
+        /// Print the positions as JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+        /// Mint the prices are shown in, token_1 of each pool by default. Every position
+        /// must then be in a pool of this mint.
+        #[arg(long)]
+        quote_in: Option<Pubkey>,
+    },
+}
+
+/// Subcommands under `raydium pool`.
+#[derive(Debug, Subcommand)]
+pub enum PoolSubcommands {
+    /// Show the live state of a pool and of its AMM config.
+    Show {
+        /// The public key of the pool.
+        pool_pubkey: Pubkey,
+        /// Print the pool as JSON instead of text.
+        #[arg(long)]
+        json: bool,
+        /// Mint the prices are shown in, token_1 of the pool by default.
+        #[arg(long)]
+        quote_in: Option<Pubkey>,
+    },
+
+    /// Find the pools of a mint pair in every AMM config.
+    Find {
+        /// One mint of the pair.
+        mint_a: Pubkey,
+        /// The other mint of the pair, in any order.
+        mint_b: Pubkey,
+        /// Print the pools as JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+        /// Mint the prices are shown in, token_1 of the pool by default.
+        #[arg(long)]
+        quote_in: Option<Pubkey>,
+    },
+
+    /// Show where the liquidity of a pool sits, as an ASCII histogram or exported
+    /// as CSV or JSON.
+    Liquidity {
+        /// The public key of the pool.
+        pool_pubkey: Pubkey,
+        /// Export the distribution in this format instead of printing the histogram.
+        #[arg(long, value_enum)]
+        format: Option<ExportFormat>,
+        /// File to write the export to, standard output by default. The histogram is
+        /// still printed when exporting to a file.
+        #[arg(long, requires = "format")]
+        output: Option<PathBuf>,
+        /// Mint the prices of the histogram and of exports are shown in, token_1 of the
+        /// pool by default.
+        #[arg(long)]
+        quote_in: Option<Pubkey>,
+    },
+}
+
+/// Token a deposit amount is given in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum DepositBase {
+    Token0,
+    Token1,
+}
+
+/// Output format of exported data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Json,
 }
 
 /// Subcommands under the Solend category.
@@ -122,52 +517,120 @@ fn main() {
     // Parse the command line arguments using Clap.
     let args = Args::parse();
 
+    // Report errors as a message and a non-zero exit code instead of panicking.
+    if let Err(error) = run(args) {
+        eprintln!("Error: {}", error);
+        process::exit(1);
+    }
+}
+
+/// Loads the configuration and dispatches the parsed subcommand.
+fn run(args: Args) -> Result<(), ClientError> {
     // Load configuration from the specified config file.
     // This file should be in TOML format and contain the necessary settings.
-    let config = client::config::Config::from_file("config.toml").unwrap();
+    // The selected profile, `RAYDIUM_*` environment variables and command-line
+    // overrides are layered on top of it, in that order.
+    let overrides = GlobalOverrides {
+        http_url: args.http_url,
+        ws_url: args.ws_url,
+        payer_path: args.payer_path,
+        raydium_v3_program: args.raydium_v3_program,
+        slippage: None,
+        commitment: args.commitment,
+        dry_run: args.dry_run.then_some(true),
+        wrap_sol: args.no_wrap_sol.then_some(false),
+    };
+    let mut config = Config::load(&args.config, args.profile.as_deref(), &overrides)?;
+    if let Some(compute_unit_price) = args.compute_unit_price {
+        config.priority_fee.compute_unit_price = compute_unit_price;
+    }
+    if let Some(compute_unit_limit) = args.compute_unit_limit {
+        config.priority_fee.compute_unit_limit = Some(compute_unit_limit);
+    }
 
     // Dispatch subcommands based on user input.
     match args.subcommand {
         Subcommands::RaydiumSubcommands(subcommand) => {
-            process_raydium_subcommands(subcommand, &config);
+            process_raydium_subcommands(subcommand, &config)
         }
         Subcommands::SolendSubcommands(subcommand) => {
-            process_solend_subcommands(subcommand, &config);
+            process_solend_subcommands(subcommand, &config)
         }
     }
 }
 
 /// Processes Solend-specific subcommands.
-fn process_solend_subcommands(subcommand: SolendSubcommands, config: &Config) {
+fn process_solend_subcommands(
+    subcommand: SolendSubcommands,
+    config: &Config,
+) -> Result<(), ClientError> {
     match subcommand {
         SolendSubcommands::Test => {
             // let solend = Pubkey::from_str("ALend7Ketfx5bxh6ghsCDXAoDrhvEmsXT3cynB6aPLgx").unwrap();
             let lending_market =
                 Pubkey::from_str("6xSkPYdpga1SgjZUemGNzDFkwbV68GSdySrP7XYShhE5").unwrap();
 
-            let rpc = RpcClient::new(config.global.http_url.to_string());
+            let rpc = config.rpc_client()?;
 
-            let account = rpc.get_account_data(&lending_market);
+            let account = rpc.get_account_data(&lending_market)?;
 
-            let lending_market_account =
-                solend_sdk::state::LendingMarket::unpack(&account.unwrap()).unwrap();
+            let lending_market_account = solend_sdk::state::LendingMarket::unpack(&account)
+                .map_err(|e| ClientError::Deserialization(format!("lending market: {}", e)))?;
 
             println!("Lending Market: {:#?}", lending_market_account);
         }
     }
+    Ok(())
 }
 
 /// Processes Raydium-specific subcommands.
-fn process_raydium_subcommands(subcommand: RaydiumSubcommands, config: &Config) {
+fn process_raydium_subcommands(
+    subcommand: RaydiumSubcommands,
+    config: &Config,
+) -> Result<(), ClientError> {
     match subcommand {
-        RaydiumSubcommands::MintToken => {
+        RaydiumSubcommands::MintToken {
+            decimals,
+            mint_authority,
+            freeze_authority,
+            token_program,
+            transfer_fee_bps,
+            max_transfer_fee,
+            interest_rate,
+            metadata_pointer,
+            metadata_address,
+            non_transferable,
+        } => {
+            let mut extensions = Vec::new();
+            if let (Some(basis_points), Some(maximum_fee)) = (transfer_fee_bps, max_transfer_fee) {
+                extensions.push(MintExtension::TransferFee {
+                    basis_points,
+                    maximum_fee,
+                });
+            }
+            if let Some(rate) = interest_rate {
+                extensions.push(MintExtension::InterestBearing { rate });
+            }
+            if metadata_pointer {
+                extensions.push(MintExtension::MetadataPointer { metadata_address });
+            }
+            if non_transferable {
+                extensions.push(MintExtension::NonTransferable);
+            }
+            let options = MintOptions {
+                decimals,
+                mint_authority,
+                freeze_authority,
+                token_program: token_program.id(),
+                extensions,
+            };
             // Create a new mint using the client module.
-            let mint = client::create_mint(&config).unwrap();
+            let mint = client::create_mint(&config, &options)?;
             println!("Mint: {}", mint);
         }
         RaydiumSubcommands::CreateTokenAccount { mint } => {
             // Create a token account for the provided mint.
-            let token_account = client::create_token_account(&config, &mint).unwrap();
+            let token_account = client::create_token_account(&config, &mint)?;
             println!("Token Account: {}", token_account);
         }
         RaydiumSubcommands::MintToTokenAccount {
@@ -176,59 +639,671 @@ fn process_raydium_subcommands(subcommand: RaydiumSubcommands, config: &Config)
             amount,
         } => {
             // Mint tokens to the specified token account.
-            client::mint_to_token_account(&config, &mint, &token_account, amount).unwrap();
+            client::mint_to_token_account(&config, &mint, &token_account, amount)?;
             println!("Minted {} tokens to account: {}", amount, token_account);
         }
         RaydiumSubcommands::IncreaseLiquidity {
-            tick_lower_price,
-            tick_upper_price,
             input_amount,
             pool_pubkey,
             slippage,
+            position_nft,
+            tick_lower_price,
+            tick_upper_price,
+            tick_lower,
+            tick_upper,
+            range_percent,
+            range_spacings,
+            full_range,
+            quote_in,
+            base,
+            other_amount_max,
         } => {
             // Increase liquidity in the pool with the specified parameters.
-            client::increase_liquidity(
+            let range = tick_range(
+                tick_lower,
+                tick_upper,
+                range_percent,
+                range_spacings,
+                full_range,
+            );
+            let position = match range {
+                Some(range) => PositionSelector::Range(range),
+                None => {
+                    position_selector(position_nft, tick_lower_price, tick_upper_price, quote_in)?
+                }
+            };
+            let deposit = match (base, other_amount_max) {
+                (DepositBase::Token0, None) => DepositAmount::Token0(input_amount),
+                (DepositBase::Token1, None) => DepositAmount::Token1(input_amount),
+                (DepositBase::Token0, Some(amount_1_max)) => DepositAmount::Both {
+                    amount_0_max: input_amount,
+                    amount_1_max,
+                },
+                (DepositBase::Token1, Some(amount_0_max)) => DepositAmount::Both {
+                    amount_0_max,
+                    amount_1_max: input_amount,
+                },
+            };
+            let nft_mint = client::increase_liquidity(
                 &config,
-                tick_lower_price,
-                tick_upper_price,
-                true, // This example assumes an additional flag (e.g., for direction).
-                input_amount,
+                position,
+                deposit,
                 pool_pubkey,
                 slippage,
-            )
-            .unwrap();
-            println!("Increased liquidity in pool: {}", pool_pubkey);
+                quote_in.as_ref(),
+            )?;
+            println!(
+                "Increased liquidity in pool: {} (position nft {})",
+                pool_pubkey, nft_mint
+            );
         }
         RaydiumSubcommands::DecreaseLiquidity {
-            tick_lower_price,
-            tick_upper_price,
-            liquidity,
             pool_pubkey,
             slippage,
+            liquidity,
+            percent,
+            amount0,
+            amount1,
+            position_nft,
+            tick_lower_price,
+            tick_upper_price,
+            quote_in,
+            yes,
         } => {
             // Decrease liquidity in the pool with the provided parameters.
+            let position =
+                position_selector(position_nft, tick_lower_price, tick_upper_price, quote_in)?;
+            let withdraw = match (liquidity, percent, amount0, amount1) {
+                (Some(liquidity), _, _, _) => WithdrawAmount::Liquidity(liquidity),
+                (_, Some(basis_points), _, _) => WithdrawAmount::BasisPoints(basis_points),
+                (_, _, Some(amount_0), _) => WithdrawAmount::Token0(amount_0),
+                (_, _, _, Some(amount_1)) => WithdrawAmount::Token1(amount_1),
+                _ => WithdrawAmount::All,
+            };
+            let ask = !yes && !config.global.dry_run;
             client::decrease_liquidity(
                 &config,
-                tick_lower_price,
-                tick_upper_price,
-                liquidity,
+                position,
+                withdraw,
                 pool_pubkey,
                 slippage,
-            )
-            .unwrap();
+                |preview| {
+                    print_withdrawal_preview(preview);
+                    !ask || confirm("Withdraw?")
+                },
+            )?;
             println!("Decreased liquidity in pool: {}", pool_pubkey);
         }
+        RaydiumSubcommands::Rebalance {
+            pool_pubkey,
+            slippage,
+            position_nft,
+            tick_lower_price,
+            tick_upper_price,
+            tick_lower,
+            tick_upper,
+            range_percent,
+            range_spacings,
+            full_range,
+            quote_in,
+            swap,
+        } => {
+            // Withdraw the position and reopen it in the new range.
+            let new_range = match tick_range(
+                tick_lower,
+                tick_upper,
+                range_percent,
+                range_spacings,
+                full_range,
+            ) {
+                Some(range) => PositionSelector::Range(range),
+                None => position_selector(None, tick_lower_price, tick_upper_price, quote_in)?,
+            };
+            let rebalanced = client::rebalance(
+                &config,
+                PositionSelector::NftMint(position_nft),
+                new_range,
+                pool_pubkey,
+                swap,
+                slippage,
+            )?;
+            print_rebalance(&rebalanced, quote_in.as_ref())?;
+        }
         RaydiumSubcommands::CreatePool {
             config_index,
             price,
             mint0,
             mint1,
             open_time,
+            quote_in,
         } => {
-            // Create a new pool with the provided configuration and parameters.
-            let pool =
-                client::create_pool(&config, config_index, price, mint0, mint1, open_time).unwrap();
+            // Create a new pool with the provided configuration and parameters. A price
+            // quoted in mint0 is the price of mint1 in mint0.
+            let (mint0, mint1) = if price_inverted(&mint0, &mint1, quote_in.as_ref())? {
+                (mint1, mint0)
+            } else {
+                (mint0, mint1)
+            };
+            let pool = client::create_pool(&config, config_index, &price, mint0, mint1, open_time)?;
             println!("Pool created: {}", pool);
         }
+        RaydiumSubcommands::LaunchPool {
+            config_index,
+            price,
+            mint0,
+            mint1,
+            tick_lower_price,
+            tick_upper_price,
+            amount0,
+            amount1,
+            open_time,
+            quote_in,
+        } => {
+            // Create the pool and deposit its initial liquidity atomically. Prices quoted
+            // in mint0 are prices of mint1 in mint0.
+            let (mint0, mint1, amount0, amount1) =
+                if price_inverted(&mint0, &mint1, quote_in.as_ref())? {
+                    (mint1, mint0, amount1, amount0)
+                } else {
+                    (mint0, mint1, amount0, amount1)
+                };
+            let launched = client::launch_pool(
+                &config,
+                config_index,
+                &price,
+                mint0,
+                mint1,
+                &tick_lower_price,
+                &tick_upper_price,
+                amount0,
+                amount1,
+                open_time,
+            )?;
+            println!("Pool created: {}", launched.pool);
+            println!("Position NFT: {}", launched.nft_mint);
+        }
+        RaydiumSubcommands::Swap {
+            pool_pubkey,
+            input_mint,
+            amount,
+            slippage,
+            exact_out,
+            limit_price,
+            quote_in,
+        } => {
+            // Swap against the pool in the requested mode.
+            client::swap(
+                &config,
+                pool_pubkey,
+                input_mint,
+                amount,
+                !exact_out,
+                limit_price.as_deref(),
+                quote_in.as_ref(),
+                slippage,
+            )?;
+            println!("Swapped in pool: {}", pool_pubkey);
+        }
+        RaydiumSubcommands::Quote {
+            pool_pubkey,
+            input_mint,
+            amount,
+            exact_out,
+            limit_price,
+            quote_in,
+            accounts_dir,
+            save_accounts,
+        } => {
+            // Simulate the swap without sending anything.
+            let quote = client::quote(
+                &config,
+                pool_pubkey,
+                input_mint,
+                amount,
+                !exact_out,
+                limit_price.as_deref(),
+                quote_in.as_ref(),
+                accounts_dir.as_deref(),
+                save_accounts.as_deref(),
+            )?;
+            println!("Amount in: {}", quote.amount_in);
+            println!("Amount out: {}", quote.amount_out);
+            println!("Fee paid: {}", quote.fee_amount);
+            println!(
+                "Price: {} -> {} (sqrt_price_x64: {} -> {})",
+                quote.price_before,
+                quote.price_after,
+                quote.sqrt_price_x64_before,
+                quote.sqrt_price_x64_after
+            );
+            println!("Price impact: {:.4}%", quote.price_impact * 100.0);
+        }
+        RaydiumSubcommands::Collect {
+            pool_pubkey,
+            position_nft,
+            all: _,
+        } => {
+            // Without `--position-nft`, `--all` is set and the whole pool is swept.
+            let results = client::collect(&config, pool_pubkey, position_nft)?;
+            for result in results {
+                println!(
+                    "Collected from position nft {}: {}",
+                    result.nft_mint, result.signature
+                );
+                println!("  token_0: {}", result.amount_0);
+                println!("  token_1: {}", result.amount_1);
+                for reward in result.rewards {
+                    println!("  reward {}: {}", reward.mint, reward.amount);
+                }
+            }
+        }
+        RaydiumSubcommands::Positions(PositionsSubcommands::List { json, quote_in }) => {
+            let positions = client::list_positions(&config)?
+                .iter()
+                .map(|position| quote_position(position, quote_in.as_ref()))
+                .collect::<Result<Vec<_>, _>>()?;
+            if json {
+                let output = serde_json::to_string_pretty(&positions)
+                    .map_err(|e| ClientError::Deserialization(format!("positions: {}", e)))?;
+                println!("{}", output);
+            } else {
+                print_positions_table(&positions);
+            }
+        }
+        RaydiumSubcommands::Pool(PoolSubcommands::Show {
+            pool_pubkey,
+            json,
+            quote_in,
+        }) => {
+            let pool = quote_pool(
+                &client::get_pool_summary(&config, pool_pubkey)?,
+                quote_in.as_ref(),
+            )?;
+            if json {
+                let output = serde_json::to_string_pretty(&pool)
+                    .map_err(|e| ClientError::Deserialization(format!("pool: {}", e)))?;
+                println!("{}", output);
+            } else {
+                print_pool(&pool);
+            }
+        }
+        RaydiumSubcommands::Pool(PoolSubcommands::Find {
+            mint_a,
+            mint_b,
+            json,
+            quote_in,
+        }) => {
+            let pools = client::find_pools(&config, mint_a, mint_b)?
+                .iter()
+                .map(|pool| quote_pool(pool, quote_in.as_ref()))
+                .collect::<Result<Vec<_>, _>>()?;
+            if json {
+                let output = serde_json::to_string_pretty(&pools)
+                    .map_err(|e| ClientError::Deserialization(format!("pools: {}", e)))?;
+                println!("{}", output);
+            } else if pools.is_empty() {
+                println!("No pool found for {} / {}", mint_a, mint_b);
+            } else {
+                for pool in &pools {
+                    println!(
+                        "Pool: {} (config {}, fee {}%, tick spacing {}) price {}, TVL {} {}",
+                        pool.pool,
+                        pool.config_index,
+                        pool.trade_fee_percent(),
+                        pool.tick_spacing,
+                        pool.price,
+                        pool.tvl,
+                        quote_token(pool)
+                    );
+                }
+            }
+        }
+        RaydiumSubcommands::Pool(PoolSubcommands::Liquidity {
+            pool_pubkey,
+            format,
+            output,
+            quote_in,
+        }) => {
+            let mut buckets = client::get_liquidity_distribution(&config, pool_pubkey)?;
+            if quote_in.is_some() {
+                let pool = client::get_pool_summary(&config, pool_pubkey)?;
+                buckets = quote_liquidity_buckets(&buckets, &pool, quote_in.as_ref())?;
+            }
+            let export = match format {
+                None => None,
+                Some(ExportFormat::Csv) => Some(client::liquidity_distribution_to_csv(&buckets)),
+                Some(ExportFormat::Json) => {
+                    Some(serde_json::to_string_pretty(&buckets).map_err(|e| {
+                        ClientError::Deserialization(format!("liquidity distribution: {}", e))
+                    })?)
+                }
+            };
+            match (export, output) {
+                (Some(export), None) => println!("{}", export),
+                (Some(export), Some(output)) => {
+                    std::fs::write(&output, export)?;
+                    println!("Liquidity distribution written to {}", output.display());
+                    print_liquidity_histogram(&buckets);
+                }
+                (None, _) => print_liquidity_histogram(&buckets),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Builds the tick range from the `--tick-lower`/`--tick-upper`, `--range-percent`,
+/// `--range-spacings` and `--full-range` options, if one of them is given.
+fn tick_range(
+    tick_lower: Option<i32>,
+    tick_upper: Option<i32>,
+    range_percent: Option<f64>,
+    range_spacings: Option<u32>,
+    full_range: bool,
+) -> Option<TickRange> {
+    match (tick_lower, tick_upper, range_percent, range_spacings) {
+        (Some(tick_lower_index), Some(tick_upper_index), _, _) => Some(TickRange::Ticks {
+            tick_lower_index,
+            tick_upper_index,
+        }),
+        (_, _, Some(percent), _) => Some(TickRange::PercentAroundPrice(percent)),
+        (_, _, _, Some(count)) => Some(TickRange::TickSpacings(count)),
+        _ => full_range.then_some(TickRange::FullRange),
+    }
+}
+
+/// Parses a percentage with at most two decimals, such as `12.5`, into basis points.
+fn parse_basis_points(value: &str) -> Result<u16, String> {
+    let invalid = || format!("{} is not a percentage with at most two decimals", value);
+    let (integer, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if integer.is_empty() && fraction.is_empty()
+        || fraction.len() > 2
+        || !integer
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    format!("{}{:0<2}", integer, fraction)
+        .parse()
+        .map_err(|_| invalid())
+}
+
+/// Builds the position selector from the `--position-nft`, price bound and
+/// `--quote-in` options.
+fn position_selector(
+    position_nft: Option<Pubkey>,
+    tick_lower_price: Option<String>,
+    tick_upper_price: Option<String>,
+    quote_mint: Option<Pubkey>,
+) -> Result<PositionSelector, ClientError> {
+    match (position_nft, tick_lower_price, tick_upper_price) {
+        (Some(nft_mint), _, _) => Ok(PositionSelector::NftMint(nft_mint)),
+        (None, Some(tick_lower_price), Some(tick_upper_price)) => {
+            Ok(PositionSelector::PriceRange {
+                tick_lower_price,
+                tick_upper_price,
+                quote_mint,
+            })
+        }
+        _ => Err(ClientError::InvalidArgument(
+            "either --position-nft or both price bounds are required".to_string(),
+        )),
+    }
+}
+
+/// Prints the liquidity a withdrawal removes and the amounts it returns.
+fn print_withdrawal_preview(preview: &WithdrawalPreview) {
+    println!(
+        "Withdrawing liquidity {} of {} ({:.2}%){}",
+        preview.liquidity,
+        preview.position_liquidity,
+        preview.liquidity as f64 / preview.position_liquidity as f64 * 100.0,
+        if preview.closes_position() {
+            ", closing the position"
+        } else {
+            ""
+        }
+    );
+    println!(
+        "  token_0: {} expected, at least {} after slippage and transfer fee",
+        preview.amount_0_expected, preview.amount_0_min
+    );
+    println!(
+        "  token_1: {} expected, at least {} after slippage and transfer fee",
+        preview.amount_1_expected, preview.amount_1_min
+    );
+}
+
+/// Prints the range, liquidity and token amounts of the positions before and after a
+/// rebalance, with range prices in `quote_mint`, token_1 by default.
+fn print_rebalance(
+    rebalanced: &RebalanceResult,
+    quote_mint: Option<&Pubkey>,
+) -> Result<(), ClientError> {
+    let inverted = price_inverted(
+        &rebalanced.token_mint_0,
+        &rebalanced.token_mint_1,
+        quote_mint,
+    )?;
+    let old_prices = tick_range_to_quoted_prices(
+        rebalanced.old_tick_lower_index,
+        rebalanced.old_tick_upper_index,
+        rebalanced.mint_decimals_0,
+        rebalanced.mint_decimals_1,
+        inverted,
+    )?;
+    let new_prices = tick_range_to_quoted_prices(
+        rebalanced.tick_lower_index,
+        rebalanced.tick_upper_index,
+        rebalanced.mint_decimals_0,
+        rebalanced.mint_decimals_1,
+        inverted,
+    )?;
+    println!(
+        "Before: ticks {} to {} (prices {} to {}), liquidity {}, token_0 {}, token_1 {}",
+        rebalanced.old_tick_lower_index,
+        rebalanced.old_tick_upper_index,
+        old_prices.0,
+        old_prices.1,
+        rebalanced.old_liquidity,
+        rebalanced.withdrawn.0,
+        rebalanced.withdrawn.1
+    );
+    if let Some(swap) = &rebalanced.swap {
+        let (input_token, output_token) = if swap.zero_for_one {
+            ("token_0", "token_1")
+        } else {
+            ("token_1", "token_0")
+        };
+        println!(
+            "Swap:   {} {} for at least {} {}",
+            swap.amount_in, input_token, swap.amount_out_min, output_token
+        );
+    }
+    println!(
+        "After:  ticks {} to {} (prices {} to {}), liquidity {}, token_0 {}, token_1 {}",
+        rebalanced.tick_lower_index,
+        rebalanced.tick_upper_index,
+        new_prices.0,
+        new_prices.1,
+        rebalanced.liquidity,
+        rebalanced.deposited.0,
+        rebalanced.deposited.1
+    );
+    println!(
+        "Moved position {} to position {}",
+        rebalanced.old_nft_mint, rebalanced.nft_mint
+    );
+    Ok(())
+}
+
+/// Asks a yes or no question on standard output and reads the answer from standard
+/// input. Anything but `y` or `yes`, including a read error, is a no.
+fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    let mut answer = String::new();
+    io::stdout().flush().is_ok()
+        && io::stdin().read_line(&mut answer).is_ok()
+        && matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
+
+/// Header of the positions table.
+const POSITIONS_TABLE_HEADER: [&str; 9] = [
+    "NFT",
+    "POOL",
+    "RANGE",
+    "PRICE",
+    "LIQUIDITY",
+    "AMOUNTS",
+    "IN RANGE",
+    "FEES OWED",
+    "REWARDS OWED",
+];
+
+/// Prints one row per position, in the order they were found, under a header. Columns
+/// are padded to their widest cell.
+fn print_positions_table(positions: &[client::PositionSummary]) {
+    if positions.is_empty() {
+        println!("No positions found");
+        return;
+    }
+    let header = POSITIONS_TABLE_HEADER.map(String::from);
+    let rows: Vec<[String; 9]> = positions
+        .iter()
+        .map(|position| {
+            let rewards: Vec<String> = position
+                .rewards
+                .iter()
+                .map(|reward| format!("{} {}", reward.amount, reward.mint))
+                .collect();
+            [
+                position.nft_mint.to_string(),
+                position.pool.to_string(),
+                format!("{} - {}", position.price_lower, position.price_upper),
+                position.price_current.clone(),
+                position.liquidity.to_string(),
+                format!("{} / {}", position.amount_0, position.amount_1),
+                if position.in_range { "yes" } else { "no" }.to_string(),
+                format!("{} / {}", position.fees_owed_0, position.fees_owed_1),
+                if rewards.is_empty() {
+                    "-".to_string()
+                } else {
+                    rewards.join(", ")
+                },
+            ]
+        })
+        .collect();
+
+    let mut widths = [0; 9];
+    for row in std::iter::once(&header).chain(&rows) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    for row in std::iter::once(&header).chain(&rows) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    }
+}
+
+/// Name of the token a pool's price and TVL are given in.
+fn quote_token(pool: &client::PoolSummary) -> &'static str {
+    if pool.quote_mint == pool.mint_0 {
+        "token_0"
+    } else {
+        "token_1"
+    }
+}
+
+/// Prints the state of a pool in a human-readable form, with its price and TVL in its
+/// quote mint.
+fn print_pool(pool: &client::PoolSummary) {
+    let quote_token = quote_token(pool);
+    println!("Pool: {}", pool.pool);
+    println!(
+        "  AMM config:     {} (index {})",
+        pool.amm_config, pool.config_index
+    );
+    println!(
+        "  Mints:          {} ({} decimals) / {} ({} decimals)",
+        pool.mint_0, pool.decimals_0, pool.mint_1, pool.decimals_1
+    );
+    println!("  Tick spacing:   {}", pool.tick_spacing);
+    println!(
+        "  Fee rate:       {}% (protocol {}, fund {})",
+        pool.trade_fee_percent(),
+        pool.protocol_fee_rate,
+        pool.fund_fee_rate
+    );
+    println!("  Current tick:   {}", pool.tick_current);
+    println!(
+        "  Price:          {} {} (sqrt_price_x64 {})",
+        pool.price, quote_token, pool.sqrt_price_x64
+    );
+    println!("  Liquidity:      {}", pool.liquidity);
+    println!(
+        "  Vault 0:        {} ({})",
+        pool.vault_amount_0, pool.vault_0
+    );
+    println!(
+        "  Vault 1:        {} ({})",
+        pool.vault_amount_1, pool.vault_1
+    );
+    println!("  TVL:            {} {}", pool.tvl, quote_token);
+    println!(
+        "  Protocol fees:  {} / {}",
+        pool.protocol_fees_owed_0, pool.protocol_fees_owed_1
+    );
+    println!(
+        "  Fund fees:      {} / {}",
+        pool.fund_fees_owed_0, pool.fund_fees_owed_1
+    );
+    println!("  Status:         {}", pool.status);
+    println!("  Open time:      {}", pool.open_time);
+    for reward in &pool.rewards {
+        println!(
+            "  Reward:         {} ({}/s, {} - {}, emitted {}, claimed {})",
+            reward.mint,
+            reward.emissions_per_second,
+            reward.open_time,
+            reward.end_time,
+            reward.total_emissioned,
+            reward.claimed
+        );
+    }
+}
+
+/// Width, in characters, of the longest bar of the liquidity histogram.
+const HISTOGRAM_WIDTH: usize = 50;
+
+/// Prints one bar per liquidity bucket, scaled to the most liquid one. The bucket
+/// holding the current price is marked with `*`.
+fn print_liquidity_histogram(buckets: &[client::LiquidityBucket]) {
+    let max_liquidity = buckets.iter().map(|bucket| bucket.liquidity).max();
+    let max_liquidity = match max_liquidity {
+        Some(max_liquidity) if max_liquidity > 0 => max_liquidity,
+        _ => {
+            println!("No liquidity");
+            return;
+        }
+    };
+    for bucket in buckets {
+        let share = bucket.liquidity as f64 / max_liquidity as f64;
+        let width = (share * HISTOGRAM_WIDTH as f64).ceil() as usize;
+        println!(
+            "{}{:>12} - {:<12} |{:<width$}| {}",
+            if bucket.is_current { "*" } else { " " },
+            bucket.price_lower,
+            bucket.price_upper,
+            "#".repeat(width),
+            bucket.liquidity,
+            width = HISTOGRAM_WIDTH
+        );
     }
 }
@@ -1,12 +1,12 @@
 use anchor_client::{Client, Cluster};
-use anyhow::Result;
-use solana_client::rpc_client::RpcClient;
-use solana_sdk::{
-    instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer,
-    transaction::Transaction,
-};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer};
 
-use crate::{config::Config, rpc::send_txn, utils::read_keypair_file};
+use crate::{
+    config::Config,
+    error::Result,
+    rpc::send_txn,
+    utils::{get_mint_token_program, read_keypair_file},
+};
 
 pub fn mint_to_token_account(
     config: &Config,
@@ -14,21 +14,22 @@ pub fn mint_to_token_account(
     token_account: &Pubkey,
     amount: u64,
 ) -> Result<()> {
-    let payer = read_keypair_file(&config.global.payer_path).unwrap();
-    let rpc_client = RpcClient::new(config.global.http_url.to_string());
+    let payer = read_keypair_file(&config.global.payer_path)?;
+    let rpc_client = config.rpc_client()?;
 
-    let mint_to_instr =
-        spl_token_mint_to_instr(&config, &payer, &mint, &token_account, amount, &payer)?;
+    let token_program = get_mint_token_program(&rpc_client, mint)?;
+    let mint_to_instr = spl_token_mint_to_instr(
+        &config,
+        &payer,
+        &token_program,
+        &mint,
+        &token_account,
+        amount,
+        &payer,
+    )?;
 
     let signers = vec![&payer];
-    let recent_hash = rpc_client.get_latest_blockhash()?;
-    let txn = Transaction::new_signed_with_payer(
-        &mint_to_instr,
-        Some(&payer.pubkey()),
-        &signers,
-        recent_hash,
-    );
-    let _ = send_txn(&rpc_client, &txn, true)?;
+    let _ = send_txn(config, &rpc_client, &mint_to_instr, &payer, &signers)?;
 
     Ok(())
 }
@@ -36,6 +37,7 @@ pub fn mint_to_token_account(
 pub fn spl_token_mint_to_instr(
     config: &Config,
     payer: &Keypair,
+    token_program: &Pubkey,
     mint: &Pubkey,
     token_account: &Pubkey,
     amount: u64,
@@ -44,7 +46,7 @@ pub fn spl_token_mint_to_instr(
     let url = Cluster::Custom(config.global.http_url.clone(), config.global.ws_url.clone());
     let client = Client::new(url, payer);
 
-    let program = client.program(spl_token_2022::id())?;
+    let program = client.program(*token_program)?;
 
     let instructions = program
         .request()
@@ -0,0 +1,393 @@
+use anchor_lang::Discriminator;
+use raydium_amm_v3::states::{
+    AmmConfig, PoolState, TickArrayBitmapExtension, TickArrayState, FEE_RATE_DENOMINATOR_VALUE,
+};
+use serde::Serialize;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::utils::deserialize_anchor_account;
+use crate::utils::get_anchor_account;
+use crate::utils::get_multiple_accounts_chunked;
+use crate::utils::get_pool_key;
+use crate::utils::get_tick_array_bitmap;
+use crate::utils::get_tick_array_key;
+use crate::utils::get_token_balances;
+use crate::utils::sort_mints;
+use crate::utils::MAX_MULTIPLE_ACCOUNTS;
+use crate::{
+    config::Config,
+    error::{ClientError, Result},
+    price::{sqrt_price_x64_to_price, tick_to_price},
+    utils::{vaults_value, x64_to_price},
+};
+
+/// A reward stream of a pool.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PoolReward {
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub mint: Pubkey,
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub vault: Pubkey,
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub authority: Pubkey,
+    pub state: u8,
+    pub open_time: u64,
+    pub end_time: u64,
+    /// Reward tokens, in base units, emitted per second to the whole pool.
+    pub emissions_per_second: f64,
+    pub total_emissioned: u64,
+    pub claimed: u64,
+}
+
+/// The live state of a pool and of its AMM config.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PoolSummary {
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub pool: Pubkey,
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub amm_config: Pubkey,
+    pub config_index: u16,
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub mint_0: Pubkey,
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub mint_1: Pubkey,
+    pub decimals_0: u8,
+    pub decimals_1: u8,
+    pub tick_spacing: u16,
+    /// Fee rates in hundredths of a basis point, as stored in the AMM config.
+    pub trade_fee_rate: u32,
+    pub protocol_fee_rate: u32,
+    pub fund_fee_rate: u32,
+    pub tick_current: i32,
+    pub sqrt_price_x64: u128,
+    /// Mint the price and the TVL are given in: token_1, or token_0 once quoted in it
+    /// with [`quote_pool`](crate::utils::quote_pool).
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub quote_mint: Pubkey,
+    /// Price of the other token in `quote_mint`, adjusted for the mint decimals.
+    pub price: String,
+    pub liquidity: u128,
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub vault_0: Pubkey,
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub vault_1: Pubkey,
+    pub vault_amount_0: u64,
+    pub vault_amount_1: u64,
+    /// Value of both vaults in whole tokens of `quote_mint`.
+    pub tvl: f64,
+    pub rewards: Vec<PoolReward>,
+    pub protocol_fees_owed_0: u64,
+    pub protocol_fees_owed_1: u64,
+    pub fund_fees_owed_0: u64,
+    pub fund_fees_owed_1: u64,
+    pub status: u8,
+    pub open_time: u64,
+}
+
+impl PoolSummary {
+    /// Returns the trade fee rate as a percentage.
+    pub fn trade_fee_percent(&self) -> f64 {
+        self.trade_fee_rate as f64 * 100.0 / FEE_RATE_DENOMINATOR_VALUE as f64
+    }
+}
+
+/// Builds the summary of `pool` from its state, its AMM config and its vault balances.
+pub fn summarize_pool(
+    pool_pubkey: Pubkey,
+    pool: &PoolState,
+    amm_config: &AmmConfig,
+    vault_amount_0: u64,
+    vault_amount_1: u64,
+) -> Result<PoolSummary> {
+    let reward_infos = pool.reward_infos;
+    let rewards = reward_infos
+        .iter()
+        .filter(|reward_info| reward_info.token_mint != Pubkey::default())
+        .map(|reward_info| PoolReward {
+            mint: reward_info.token_mint,
+            vault: reward_info.token_vault,
+            authority: reward_info.authority,
+            state: reward_info.reward_state,
+            open_time: reward_info.open_time,
+            end_time: reward_info.end_time,
+            emissions_per_second: x64_to_price(reward_info.emissions_per_second_x64),
+            total_emissioned: reward_info.reward_total_emissioned,
+            claimed: reward_info.reward_claimed,
+        })
+        .collect();
+
+    let price = sqrt_price_x64_to_price(
+        pool.sqrt_price_x64,
+        pool.mint_decimals_0,
+        pool.mint_decimals_1,
+    );
+    let tvl = vaults_value(
+        vault_amount_0,
+        vault_amount_1,
+        pool.sqrt_price_x64,
+        pool.mint_decimals_0,
+        pool.mint_decimals_1,
+        false,
+    );
+
+    Ok(PoolSummary {
+        pool: pool_pubkey,
+        amm_config: pool.amm_config,
+        config_index: amm_config.index,
+        mint_0: pool.token_mint_0,
+        mint_1: pool.token_mint_1,
+        decimals_0: pool.mint_decimals_0,
+        decimals_1: pool.mint_decimals_1,
+        tick_spacing: pool.tick_spacing,
+        trade_fee_rate: amm_config.trade_fee_rate,
+        protocol_fee_rate: amm_config.protocol_fee_rate,
+        fund_fee_rate: amm_config.fund_fee_rate,
+        tick_current: pool.tick_current,
+        sqrt_price_x64: pool.sqrt_price_x64,
+        quote_mint: pool.token_mint_1,
+        price,
+        liquidity: pool.liquidity,
+        vault_0: pool.token_vault_0,
+        vault_1: pool.token_vault_1,
+        vault_amount_0,
+        vault_amount_1,
+        tvl,
+        rewards,
+        protocol_fees_owed_0: pool.protocol_fees_token_0,
+        protocol_fees_owed_1: pool.protocol_fees_token_1,
+        fund_fees_owed_0: pool.fund_fees_token_0,
+        fund_fees_owed_1: pool.fund_fees_token_1,
+        status: pool.status,
+        open_time: pool.open_time,
+    })
+}
+
+/// Loads a pool, its AMM config and its vault balances.
+pub fn get_pool_summary(config: &Config, pool_pubkey: Pubkey) -> Result<PoolSummary> {
+    let rpc_client = config.rpc_client()?;
+
+    let pool: PoolState = get_anchor_account(&rpc_client, &pool_pubkey)?;
+    let amm_config: AmmConfig = get_anchor_account(&rpc_client, &pool.amm_config)?;
+    let vault_amounts = get_token_balances(&rpc_client, &[pool.token_vault_0, pool.token_vault_1])?;
+
+    summarize_pool(
+        pool_pubkey,
+        &pool,
+        &amm_config,
+        vault_amounts[0],
+        vault_amounts[1],
+    )
+}
+
+/// Fetches every `AmmConfig` account of the program with its key.
+pub fn fetch_amm_configs(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+) -> Result<Vec<(Pubkey, AmmConfig)>> {
+    let accounts = rpc_client.get_program_accounts_with_config(
+        program_id,
+        RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                0,
+                &AmmConfig::DISCRIMINATOR,
+            ))]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..Default::default()
+            },
+            with_context: None,
+        },
+    )?;
+    let mut amm_configs = accounts
+        .iter()
+        .map(|(key, account)| Ok((*key, deserialize_anchor_account::<AmmConfig>(account)?)))
+        .collect::<Result<Vec<_>>>()?;
+    amm_configs.sort_by_key(|(_, amm_config)| amm_config.index);
+    Ok(amm_configs)
+}
+
+/// Finds the pools of a mint pair in every AMM config of the program, in any mint order.
+pub fn find_pools(config: &Config, mint_a: Pubkey, mint_b: Pubkey) -> Result<Vec<PoolSummary>> {
+    let rpc_client = config.rpc_client()?;
+    let program_id = config.raydium_v3_program_id()?;
+
+    let (mint0, mint1) = sort_mints(mint_a, mint_b);
+    let amm_configs = fetch_amm_configs(&rpc_client, &program_id)?;
+    let pool_keys: Vec<Pubkey> = amm_configs
+        .iter()
+        .map(|(amm_config_key, _)| get_pool_key(amm_config_key, &mint0, &mint1, &program_id))
+        .collect();
+    let pool_accounts = get_multiple_accounts_chunked(&rpc_client, &pool_keys)?;
+
+    let mut pools = Vec::new();
+    for ((pool_key, account), (_, amm_config)) in
+        pool_keys.into_iter().zip(pool_accounts).zip(&amm_configs)
+    {
+        if let Some(account) = account {
+            pools.push((
+                pool_key,
+                deserialize_anchor_account::<PoolState>(&account)?,
+                amm_config,
+            ));
+        }
+    }
+
+    let vaults: Vec<Pubkey> = pools
+        .iter()
+        .flat_map(|(_, pool, _)| [pool.token_vault_0, pool.token_vault_1])
+        .collect();
+    let vault_amounts = get_token_balances(&rpc_client, &vaults)?;
+
+    pools
+        .iter()
+        .zip(vault_amounts.chunks(2))
+        .map(|((pool_key, pool, amm_config), amounts)| {
+            summarize_pool(*pool_key, pool, amm_config, amounts[0], amounts[1])
+        })
+        .collect()
+}
+
+/// Active liquidity between two consecutive initialized ticks.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LiquidityBucket {
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    /// Prices of token_0 in token_1 at the bucket bounds, adjusted for decimals.
+    pub price_lower: String,
+    pub price_upper: String,
+    pub liquidity: u128,
+    /// Whether the pool's current tick is inside the bucket.
+    pub is_current: bool,
+}
+
+/// Fetches every initialized tick array of a pool, found by walking the pool's tick
+/// array bitmap and its bitmap extension in both directions from the current tick.
+pub fn fetch_tick_arrays(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    pool_pubkey: Pubkey,
+    pool: &PoolState,
+) -> Result<Vec<TickArrayState>> {
+    let tickarray_bitmap_extension_key = get_tick_array_bitmap(
+        &pool.amm_config,
+        &pool.token_mint_0,
+        &pool.token_mint_1,
+        program_id,
+    );
+    let tickarray_bitmap_extension = match rpc_client
+        .get_multiple_accounts(&[tickarray_bitmap_extension_key])?
+        .remove(0)
+    {
+        Some(account) => Some(deserialize_anchor_account::<TickArrayBitmapExtension>(
+            &account,
+        )?),
+        None => None,
+    };
+
+    // the array of the current tick is only present if it is initialized
+    let current_start_index =
+        TickArrayState::get_array_start_index(pool.tick_current, pool.tick_spacing.into());
+    let mut tick_array_start_indexes = vec![current_start_index];
+    for zero_for_one in [true, false] {
+        let mut start_index = current_start_index;
+        while let Some(index) = pool.next_initialized_tick_array_start_index(
+            &tickarray_bitmap_extension,
+            start_index,
+            zero_for_one,
+        )? {
+            tick_array_start_indexes.push(index);
+            start_index = index;
+        }
+    }
+    tick_array_start_indexes.sort_unstable();
+
+    let tick_array_keys: Vec<Pubkey> = tick_array_start_indexes
+        .iter()
+        .map(|index| get_tick_array_key(&pool_pubkey, *index, program_id))
+        .collect();
+    get_multiple_accounts_chunked(rpc_client, &tick_array_keys)?
+        .into_iter()
+        .flatten()
+        .map(|account| deserialize_anchor_account::<TickArrayState>(&account))
+        .collect()
+}
+
+/// Computes the active liquidity between every pair of consecutive initialized ticks
+/// by accumulating the net liquidity of the ticks from the lowest one.
+///
+/// `tick_arrays` must hold every initialized tick array of the pool.
+pub fn liquidity_distribution(
+    pool: &PoolState,
+    tick_arrays: &[TickArrayState],
+) -> Result<Vec<LiquidityBucket>> {
+    let mut ticks: Vec<(i32, i128)> = tick_arrays
+        .iter()
+        .flat_map(|tick_array| tick_array.ticks)
+        .filter(|tick| tick.liquidity_gross != 0)
+        .map(|tick| (tick.tick, tick.liquidity_net))
+        .collect();
+    ticks.sort_unstable_by_key(|(tick, _)| *tick);
+
+    let decimals_0 = pool.mint_decimals_0;
+    let decimals_1 = pool.mint_decimals_1;
+    let tick_current = pool.tick_current;
+    let price_at_tick = |tick: i32| tick_to_price(tick, decimals_0, decimals_1);
+
+    let mut buckets = Vec::with_capacity(ticks.len().saturating_sub(1));
+    let mut liquidity: i128 = 0;
+    for window in ticks.windows(2) {
+        let (tick_lower, liquidity_net) = window[0];
+        let tick_upper = window[1].0;
+        liquidity = liquidity.checked_add(liquidity_net).ok_or_else(|| {
+            ClientError::MathOverflow(format!("liquidity at tick {}", tick_lower))
+        })?;
+        buckets.push(LiquidityBucket {
+            tick_lower,
+            tick_upper,
+            price_lower: price_at_tick(tick_lower)?,
+            price_upper: price_at_tick(tick_upper)?,
+            liquidity: u128::try_from(liquidity).map_err(|_| {
+                ClientError::MathOverflow(format!("negative liquidity at tick {}", tick_lower))
+            })?,
+            is_current: tick_lower <= tick_current && tick_current < tick_upper,
+        });
+    }
+    Ok(buckets)
+}
+
+/// Loads a pool with all its initialized tick arrays and returns its liquidity distribution.
+pub fn get_liquidity_distribution(
+    config: &Config,
+    pool_pubkey: Pubkey,
+) -> Result<Vec<LiquidityBucket>> {
+    let rpc_client = config.rpc_client()?;
+    let program_id = config.raydium_v3_program_id()?;
+
+    let pool: PoolState = get_anchor_account(&rpc_client, &pool_pubkey)?;
+    let tick_arrays = fetch_tick_arrays(&rpc_client, &program_id, pool_pubkey, &pool)?;
+    liquidity_distribution(&pool, &tick_arrays)
+}
+
+/// Formats a liquidity distribution as CSV, with a header row.
+pub fn liquidity_distribution_to_csv(buckets: &[LiquidityBucket]) -> String {
+    let mut csv =
+        String::from("tick_lower,tick_upper,price_lower,price_upper,liquidity,is_current\n");
+    for bucket in buckets {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            bucket.tick_lower,
+            bucket.tick_upper,
+            bucket.price_lower,
+            bucket.price_upper,
+            bucket.liquidity,
+            bucket.is_current
+        ));
+    }
+    csv
+}
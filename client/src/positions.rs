@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+
+use raydium_amm_v3::libraries::big_num::U256;
+use raydium_amm_v3::libraries::liquidity_math;
+use raydium_amm_v3::states::{
+    PersonalPositionState, PoolState, TickArrayState, TickState, REWARD_NUM,
+};
+use serde::Serialize;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
+
+use crate::utils::deserialize_anchor_account;
+use crate::utils::get_all_nft_and_position_by_owner;
+use crate::utils::get_multiple_accounts_chunked;
+use crate::utils::get_tick_array_key;
+use crate::utils::serialize_pubkey;
+use crate::{
+    config::Config,
+    error::{ClientError, Result},
+    price::{sqrt_price_x64_to_price, tick_to_price},
+    utils::read_keypair_file,
+};
+
+/// Uncollected reward of a single pool reward stream.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PositionReward {
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+/// A position owned by the payer, joined with the state of its pool.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PositionSummary {
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub position: Pubkey,
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub nft_mint: Pubkey,
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub nft_account: Pubkey,
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub pool: Pubkey,
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub mint_0: Pubkey,
+    #[serde(serialize_with = "serialize_pubkey")]
+    pub mint_1: Pubkey,
+    pub decimals_0: u8,
+    pub decimals_1: u8,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    /// Sqrt price of the pool.
+    pub sqrt_price_x64: u128,
+    /// Prices of token_0 in token_1 at the range bounds and at the pool's current price.
+    pub price_lower: String,
+    pub price_upper: String,
+    pub price_current: String,
+    pub liquidity: u128,
+    /// Token amounts the position would return if fully withdrawn now.
+    pub amount_0: u64,
+    pub amount_1: u64,
+    pub in_range: bool,
+    pub fees_owed_0: u64,
+    pub fees_owed_1: u64,
+    pub rewards: Vec<PositionReward>,
+}
+
+/// Uncollected fees and rewards of a position.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PositionFees {
+    pub fees_owed_0: u64,
+    pub fees_owed_1: u64,
+    pub rewards_owed: [u64; REWARD_NUM],
+}
+
+/// Returns the tick state of `tick_index` inside `tick_array`.
+pub fn get_tick_state(
+    tick_array: &TickArrayState,
+    tick_index: i32,
+    tick_spacing: u16,
+) -> Result<TickState> {
+    let start_index = tick_array.start_tick_index;
+    let offset = (tick_index - start_index) / i32::from(tick_spacing);
+    let ticks = tick_array.ticks;
+    if tick_index < start_index || offset as usize >= ticks.len() {
+        return Err(ClientError::InvalidArgument(format!(
+            "tick {} is not in the tick array starting at {}",
+            tick_index, start_index
+        )));
+    }
+    Ok(ticks[offset as usize])
+}
+
+/// Computes the growth of a global accumulator inside `[tick_lower, tick_upper)`,
+/// the same way the program does when the position is next touched.
+fn growth_inside(
+    tick_current: i32,
+    tick_lower: i32,
+    tick_upper: i32,
+    growth_global: u128,
+    growth_outside_lower: u128,
+    growth_outside_upper: u128,
+) -> u128 {
+    let growth_below = if tick_current >= tick_lower {
+        growth_outside_lower
+    } else {
+        growth_global.wrapping_sub(growth_outside_lower)
+    };
+    let growth_above = if tick_current < tick_upper {
+        growth_outside_upper
+    } else {
+        growth_global.wrapping_sub(growth_outside_upper)
+    };
+    growth_global
+        .wrapping_sub(growth_below)
+        .wrapping_sub(growth_above)
+}
+
+/// Converts a growth delta into a token amount owed to `liquidity`.
+fn growth_to_amount(growth_delta: u128, liquidity: u128) -> Result<u64> {
+    let amount = (U256::from(growth_delta) * U256::from(liquidity)) >> 64;
+    if amount > U256::from(u64::MAX) {
+        return Err(ClientError::MathOverflow(format!(
+            "owed amount for growth {} and liquidity {}",
+            growth_delta, liquidity
+        )));
+    }
+    Ok(amount.as_u64())
+}
+
+/// Computes the fees and rewards a position could collect now.
+///
+/// Reward growth is taken as of the pool's last update, so rewards emitted since
+/// then are not included.
+pub fn get_position_fees(
+    pool: &PoolState,
+    position: &PersonalPositionState,
+    tick_lower: &TickState,
+    tick_upper: &TickState,
+) -> Result<PositionFees> {
+    let tick_current = pool.tick_current;
+    let tick_lower_index = position.tick_lower_index;
+    let tick_upper_index = position.tick_upper_index;
+    let liquidity = position.liquidity;
+
+    let fee_growth_inside_0 = growth_inside(
+        tick_current,
+        tick_lower_index,
+        tick_upper_index,
+        pool.fee_growth_global_0_x64,
+        tick_lower.fee_growth_outside_0_x64,
+        tick_upper.fee_growth_outside_0_x64,
+    );
+    let fee_growth_inside_1 = growth_inside(
+        tick_current,
+        tick_lower_index,
+        tick_upper_index,
+        pool.fee_growth_global_1_x64,
+        tick_lower.fee_growth_outside_1_x64,
+        tick_upper.fee_growth_outside_1_x64,
+    );
+    let fees_owed_0 = position
+        .token_fees_owed_0
+        .checked_add(growth_to_amount(
+            fee_growth_inside_0.wrapping_sub(position.fee_growth_inside_0_last_x64),
+            liquidity,
+        )?)
+        .ok_or_else(|| ClientError::MathOverflow("fees_owed_0".to_string()))?;
+    let fees_owed_1 = position
+        .token_fees_owed_1
+        .checked_add(growth_to_amount(
+            fee_growth_inside_1.wrapping_sub(position.fee_growth_inside_1_last_x64),
+            liquidity,
+        )?)
+        .ok_or_else(|| ClientError::MathOverflow("fees_owed_1".to_string()))?;
+
+    let reward_infos = pool.reward_infos;
+    let reward_growths_outside_lower = { tick_lower.reward_growths_outside_x64 };
+    let reward_growths_outside_upper = { tick_upper.reward_growths_outside_x64 };
+    let mut rewards_owed = [0; REWARD_NUM];
+    for i in 0..REWARD_NUM {
+        if reward_infos[i].token_mint == Pubkey::default() {
+            continue;
+        }
+        let reward_growth_inside = growth_inside(
+            tick_current,
+            tick_lower_index,
+            tick_upper_index,
+            reward_infos[i].reward_growth_global_x64,
+            reward_growths_outside_lower[i],
+            reward_growths_outside_upper[i],
+        );
+        let position_reward = &position.reward_infos[i];
+        rewards_owed[i] = position_reward
+            .reward_amount_owed
+            .checked_add(growth_to_amount(
+                reward_growth_inside.wrapping_sub(position_reward.growth_inside_last_x64),
+                liquidity,
+            )?)
+            .ok_or_else(|| ClientError::MathOverflow(format!("reward_owed_{}", i)))?;
+    }
+
+    Ok(PositionFees {
+        fees_owed_0,
+        fees_owed_1,
+        rewards_owed,
+    })
+}
+
+/// Builds the summary of a position from its pool and the tick arrays holding its bounds.
+pub fn summarize_position(
+    position_key: Pubkey,
+    nft_account: Pubkey,
+    position: &PersonalPositionState,
+    pool: &PoolState,
+    tick_array_lower: &TickArrayState,
+    tick_array_upper: &TickArrayState,
+) -> Result<PositionSummary> {
+    let tick_lower_index = position.tick_lower_index;
+    let tick_upper_index = position.tick_upper_index;
+    let tick_lower = get_tick_state(tick_array_lower, tick_lower_index, pool.tick_spacing)?;
+    let tick_upper = get_tick_state(tick_array_upper, tick_upper_index, pool.tick_spacing)?;
+    let fees = get_position_fees(pool, position, &tick_lower, &tick_upper)?;
+
+    let (amount_0, amount_1) = liquidity_math::get_delta_amounts_signed(
+        pool.tick_current,
+        pool.sqrt_price_x64,
+        tick_lower_index,
+        tick_upper_index,
+        -(position.liquidity as i128),
+    )?;
+
+    let decimals_0 = pool.mint_decimals_0;
+    let decimals_1 = pool.mint_decimals_1;
+    let reward_infos = pool.reward_infos;
+    let rewards = reward_infos
+        .iter()
+        .zip(fees.rewards_owed)
+        .filter(|(reward_info, _)| reward_info.token_mint != Pubkey::default())
+        .map(|(reward_info, amount)| PositionReward {
+            mint: reward_info.token_mint,
+            amount,
+        })
+        .collect();
+
+    Ok(PositionSummary {
+        position: position_key,
+        nft_mint: position.nft_mint,
+        nft_account,
+        pool: position.pool_id,
+        mint_0: pool.token_mint_0,
+        mint_1: pool.token_mint_1,
+        decimals_0,
+        decimals_1,
+        tick_lower_index,
+        tick_upper_index,
+        sqrt_price_x64: pool.sqrt_price_x64,
+        price_lower: tick_to_price(tick_lower_index, decimals_0, decimals_1)?,
+        price_upper: tick_to_price(tick_upper_index, decimals_0, decimals_1)?,
+        price_current: sqrt_price_x64_to_price(pool.sqrt_price_x64, decimals_0, decimals_1),
+        liquidity: position.liquidity,
+        amount_0,
+        amount_1,
+        in_range: tick_lower_index <= pool.tick_current && pool.tick_current < tick_upper_index,
+        fees_owed_0: fees.fees_owed_0,
+        fees_owed_1: fees.fees_owed_1,
+        rewards,
+    })
+}
+
+/// Fetches every position owned by `owner` together with its pool and tick arrays.
+pub fn fetch_positions(
+    rpc_client: &RpcClient,
+    owner: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<Vec<PositionSummary>> {
+    let position_nft_infos = get_all_nft_and_position_by_owner(rpc_client, owner, program_id)?;
+    let position_keys: Vec<Pubkey> = position_nft_infos
+        .iter()
+        .map(|item| item.position)
+        .collect();
+    let rsps = get_multiple_accounts_chunked(rpc_client, &position_keys)?;
+
+    // NFTs without a position account are not Raydium position NFTs.
+    let mut positions = Vec::new();
+    for (nft_info, rsp) in position_nft_infos.iter().zip(rsps) {
+        if let Some(account) = rsp {
+            let position = deserialize_anchor_account::<PersonalPositionState>(&account)?;
+            positions.push((nft_info, position));
+        }
+    }
+
+    let mut pool_keys: Vec<Pubkey> = positions
+        .iter()
+        .map(|(_, position)| position.pool_id)
+        .collect();
+    pool_keys.sort();
+    pool_keys.dedup();
+    let rsps = get_multiple_accounts_chunked(rpc_client, &pool_keys)?;
+    let mut pools = HashMap::new();
+    for (pool_key, rsp) in pool_keys.into_iter().zip(rsps) {
+        let account = rsp.ok_or(ClientError::AccountNotFound(pool_key))?;
+        pools.insert(pool_key, deserialize_anchor_account::<PoolState>(&account)?);
+    }
+
+    let mut tick_array_keys = Vec::new();
+    for (_, position) in positions.iter() {
+        let pool = &pools[&position.pool_id];
+        for tick_index in [position.tick_lower_index, position.tick_upper_index] {
+            let start_index =
+                TickArrayState::get_array_start_index(tick_index, pool.tick_spacing.into());
+            tick_array_keys.push(get_tick_array_key(
+                &position.pool_id,
+                start_index,
+                program_id,
+            ));
+        }
+    }
+    tick_array_keys.sort();
+    tick_array_keys.dedup();
+    let rsps = get_multiple_accounts_chunked(rpc_client, &tick_array_keys)?;
+    let mut tick_arrays = HashMap::new();
+    for (tick_array_key, rsp) in tick_array_keys.into_iter().zip(rsps) {
+        let account = rsp.ok_or(ClientError::AccountNotFound(tick_array_key))?;
+        tick_arrays.insert(
+            tick_array_key,
+            deserialize_anchor_account::<TickArrayState>(&account)?,
+        );
+    }
+
+    let mut summaries = Vec::with_capacity(positions.len());
+    for (nft_info, position) in positions {
+        let pool = &pools[&position.pool_id];
+        let tick_array_key = |tick_index: i32| {
+            let start_index =
+                TickArrayState::get_array_start_index(tick_index, pool.tick_spacing.into());
+            get_tick_array_key(&position.pool_id, start_index, program_id)
+        };
+        summaries.push(summarize_position(
+            nft_info.position,
+            nft_info.key,
+            &position,
+            pool,
+            &tick_arrays[&tick_array_key(position.tick_lower_index)],
+            &tick_arrays[&tick_array_key(position.tick_upper_index)],
+        )?);
+    }
+    Ok(summaries)
+}
+
+/// Lists every position owned by the configured payer.
+pub fn list_positions(config: &Config) -> Result<Vec<PositionSummary>> {
+    let payer = read_keypair_file(&config.global.payer_path)?;
+    let rpc_client = config.rpc_client()?;
+    let program_id = config.raydium_v3_program_id()?;
+
+    fetch_positions(&rpc_client, &payer.pubkey(), &program_id)
+}
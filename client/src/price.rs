@@ -0,0 +1,273 @@
+//! Exact conversions between decimal prices and the program's Q64.64 sqrt prices.
+//!
+//! A price is the amount of token_1 paid for one token_0, in whole tokens. The program
+//! stores `sqrt(raw_price) * 2^64`, where the raw price is in base units, so a price
+//! converts to `floor(sqrt(price * 10^decimals_1 / 10^decimals_0 * 2^128))`. That value is
+//! computed with big integers, without going through floating point.
+#![allow(clippy::assign_op_pattern)]
+#![allow(clippy::ptr_offset_with_cast)]
+#![allow(clippy::manual_range_contains)]
+
+use raydium_amm_v3::libraries::tick_math;
+use uint::construct_uint;
+
+use crate::error::{ClientError, Result};
+
+construct_uint! {
+    struct U1024(16);
+}
+
+/// Most significant digits accepted in a price.
+pub const MAX_PRICE_DIGITS: usize = 100;
+
+/// Number of fractional digits of `2^-128`, which is `5^128 / 10^128`.
+const Q128_DECIMALS: usize = 128;
+
+/// Fractional digits resolving the raw inverse prices that convert back to a sqrt price,
+/// which span more than `10^-78` for any `u128` sqrt price.
+const INVERSE_DECIMALS: usize = 80;
+
+/// A positive decimal number `mantissa * 10^exponent`.
+struct Decimal {
+    mantissa: U1024,
+    digits: usize,
+    exponent: i64,
+}
+
+/// Parses a positive decimal such as `12`, `0.015` or `1.5e-9`.
+fn parse_decimal(price: &str) -> Result<Decimal> {
+    let invalid = || ClientError::InvalidArgument(format!("invalid price \"{}\"", price));
+    let trimmed = price.trim();
+    let trimmed = trimmed.strip_prefix('+').unwrap_or(trimmed);
+    let (number, exponent) = match trimmed.find(|c| c == 'e' || c == 'E') {
+        Some(index) => (
+            &trimmed[..index],
+            trimmed[index + 1..].parse::<i64>().map_err(|_| invalid())?,
+        ),
+        None => (trimmed, 0),
+    };
+    let (integer, fraction) = match number.split_once('.') {
+        Some((integer, fraction)) => (integer, fraction),
+        None => (number, ""),
+    };
+    if integer.is_empty() && fraction.is_empty()
+        || !integer
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+
+    let digits = format!("{}{}", integer, fraction);
+    let digits = digits.trim_start_matches('0');
+    let significant = digits.trim_end_matches('0');
+    if significant.is_empty() {
+        return Err(ClientError::InvalidArgument(format!(
+            "price {} is not positive",
+            price
+        )));
+    }
+    if significant.len() > MAX_PRICE_DIGITS {
+        return Err(ClientError::InvalidArgument(format!(
+            "price {} has more than {} significant digits",
+            price, MAX_PRICE_DIGITS
+        )));
+    }
+    let trailing_zeros = (digits.len() - significant.len()) as i64;
+    Ok(Decimal {
+        mantissa: U1024::from_dec_str(significant).map_err(|_| invalid())?,
+        digits: significant.len(),
+        exponent: exponent
+            .checked_sub(fraction.len() as i64)
+            .and_then(|exponent| exponent.checked_add(trailing_zeros))
+            .ok_or_else(invalid)?,
+    })
+}
+
+/// Checks that `price` is a positive decimal, whatever the mint decimals.
+pub fn check_price(price: &str) -> Result<()> {
+    parse_decimal(price).map(|_| ())
+}
+
+fn pow10(exponent: usize) -> U1024 {
+    (0..exponent).fold(U1024::one(), |value, _| value * 10)
+}
+
+fn too_large(price: &str) -> ClientError {
+    ClientError::MathOverflow(format!("sqrt price of {}", price))
+}
+
+fn out_of_range(price: &str) -> ClientError {
+    ClientError::InvalidArgument(format!("price {} is out of range", price))
+}
+
+/// Takes the integer sqrt of a raw price scaled by `2^128`, which is the sqrt price of the
+/// program rounded down.
+fn raw_price_x128_to_sqrt_price_x64(raw_price_x128: U1024, price: &str) -> Result<u128> {
+    // floor(sqrt(floor(x))) == floor(sqrt(x)), so the rounding stays exact
+    let sqrt_price_x64 = raw_price_x128.integer_sqrt();
+    if sqrt_price_x64.bits() > 128 {
+        return Err(too_large(price));
+    }
+    Ok(sqrt_price_x64.low_u128())
+}
+
+/// Converts a decimal price to the sqrt price of the program, rounding down.
+///
+/// Prices too small for the Q64.64 format convert to 0; prices whose sqrt price does not
+/// fit in a `u128` are an error.
+pub fn price_to_sqrt_price_x64(price: &str, decimals_0: u8, decimals_1: u8) -> Result<u128> {
+    let decimal = parse_decimal(price)?;
+    let exponent = decimal
+        .exponent
+        .checked_add(decimals_1 as i64 - decimals_0 as i64)
+        .ok_or_else(|| out_of_range(price))?;
+
+    // the raw price is below 10^magnitude: from 10^39 its sqrt price overflows a u128,
+    // below 10^-40 it rounds down to 0
+    let magnitude = (decimal.digits as i64)
+        .checked_add(exponent)
+        .ok_or_else(|| out_of_range(price))?;
+    if magnitude > 39 {
+        return Err(too_large(price));
+    }
+    if magnitude < -39 {
+        return Ok(0);
+    }
+    let raw_price_x128 = if exponent >= 0 {
+        (decimal.mantissa * pow10(exponent as usize)) << 128
+    } else {
+        (decimal.mantissa << 128) / pow10(exponent.unsigned_abs() as usize)
+    };
+    raw_price_x128_to_sqrt_price_x64(raw_price_x128, price)
+}
+
+/// Converts a decimal price of token_1 in token_0, the inverse of the prices of the
+/// program, to the sqrt price of the program, rounding down.
+///
+/// Like [`price_to_sqrt_price_x64`], prices whose sqrt price does not fit in a `u128` are
+/// an error and the others round down, to 0 if need be.
+pub fn inverse_price_to_sqrt_price_x64(
+    price: &str,
+    decimals_0: u8,
+    decimals_1: u8,
+) -> Result<u128> {
+    let decimal = parse_decimal(price)?;
+    // the raw price is 10^exponent / mantissa
+    let exponent = (decimals_1 as i64 - decimals_0 as i64)
+        .checked_sub(decimal.exponent)
+        .ok_or_else(|| out_of_range(price))?;
+
+    // the raw price is in (10^magnitude, 10^(magnitude + 1)]
+    let magnitude = exponent
+        .checked_sub(decimal.digits as i64)
+        .ok_or_else(|| out_of_range(price))?;
+    if magnitude >= 39 {
+        return Err(too_large(price));
+    }
+    if magnitude < -40 {
+        return Ok(0);
+    }
+    let raw_price_x128 = if exponent >= 0 {
+        (pow10(exponent as usize) << 128) / decimal.mantissa
+    } else {
+        (U1024::one() << 128) / (decimal.mantissa * pow10(exponent.unsigned_abs() as usize))
+    };
+    raw_price_x128_to_sqrt_price_x64(raw_price_x128, price)
+}
+
+/// Formats `mantissa * 10^exponent` without an exponent.
+fn format_decimal(mantissa: U1024, exponent: i64) -> String {
+    let digits = mantissa.to_string();
+    let significant = digits.trim_end_matches('0');
+    if significant.is_empty() {
+        return "0".to_string();
+    }
+    let exponent = exponent + (digits.len() - significant.len()) as i64;
+    if exponent >= 0 {
+        return format!("{}{}", significant, "0".repeat(exponent as usize));
+    }
+    let fraction_len = exponent.unsigned_abs() as usize;
+    if fraction_len >= significant.len() {
+        format!(
+            "0.{}{}",
+            "0".repeat(fraction_len - significant.len()),
+            significant
+        )
+    } else {
+        let (integer, fraction) = significant.split_at(significant.len() - fraction_len);
+        format!("{}.{}", integer, fraction)
+    }
+}
+
+/// Returns the number with the most trailing zeros in `[lower, upper)`, which must not
+/// be empty.
+fn round_in_interval(lower: U1024, upper: U1024) -> U1024 {
+    // round the lower bound up to as few digits as the interval allows
+    let mut rounded = lower;
+    let mut unit = U1024::one();
+    loop {
+        let next_unit = unit * 10;
+        let next = (lower + next_unit - 1) / next_unit * next_unit;
+        if next >= upper {
+            return rounded;
+        }
+        rounded = next;
+        unit = next_unit;
+    }
+}
+
+/// Converts a sqrt price of the program to the shortest decimal price that converts
+/// back to it with [`price_to_sqrt_price_x64`].
+pub fn sqrt_price_x64_to_price(sqrt_price_x64: u128, decimals_0: u8, decimals_1: u8) -> String {
+    if sqrt_price_x64 == 0 {
+        return "0".to_string();
+    }
+    // the sqrt price converts back from any raw price in [s^2, (s + 1)^2) / 2^128, which
+    // scaled by 10^128 are the integers in [lower, upper)
+    let sqrt_price = U1024::from(sqrt_price_x64);
+    let five_128 = (0..Q128_DECIMALS).fold(U1024::one(), |value, _| value * 5);
+    let lower = sqrt_price * sqrt_price * five_128;
+    let upper = (sqrt_price + 1) * (sqrt_price + 1) * five_128;
+    format_decimal(
+        round_in_interval(lower, upper),
+        decimals_0 as i64 - decimals_1 as i64 - Q128_DECIMALS as i64,
+    )
+}
+
+/// Converts a sqrt price of the program to the shortest decimal price of token_1 in
+/// token_0 that converts back to it with [`inverse_price_to_sqrt_price_x64`].
+pub fn sqrt_price_x64_to_inverse_price(
+    sqrt_price_x64: u128,
+    decimals_0: u8,
+    decimals_1: u8,
+) -> Result<String> {
+    if sqrt_price_x64 == 0 {
+        return Err(ClientError::MathOverflow(
+            "inverse of a zero sqrt price".to_string(),
+        ));
+    }
+    // the sqrt price converts back from any raw inverse price in
+    // (2^128 / (s + 1)^2, 2^128 / s^2], which scaled by 10^INVERSE_DECIMALS and rounded
+    // are the integers in [lower, upper)
+    let sqrt_price = U1024::from(sqrt_price_x64);
+    let scale = pow10(INVERSE_DECIMALS) << 128;
+    let lower = scale / ((sqrt_price + 1) * (sqrt_price + 1)) + 1;
+    let upper = scale / (sqrt_price * sqrt_price) + 1;
+    Ok(format_decimal(
+        round_in_interval(lower, upper),
+        decimals_1 as i64 - decimals_0 as i64 - INVERSE_DECIMALS as i64,
+    ))
+}
+
+/// Returns the price at a tick as the shortest decimal that converts back to the sqrt
+/// price of the tick.
+pub fn tick_to_price(tick: i32, decimals_0: u8, decimals_1: u8) -> Result<String> {
+    let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(tick)?;
+    Ok(sqrt_price_x64_to_price(
+        sqrt_price_x64,
+        decimals_0,
+        decimals_1,
+    ))
+}
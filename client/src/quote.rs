@@ -0,0 +1,617 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::ops::Neg;
+use std::path::Path;
+
+use anchor_lang::Discriminator;
+use raydium_amm_v3::libraries::liquidity_math;
+use raydium_amm_v3::libraries::swap_math;
+use raydium_amm_v3::libraries::tick_math;
+use raydium_amm_v3::states::{
+    AmmConfig, PoolState, TickArrayBitmapExtension, TickArrayState, TickState,
+};
+use solana_account_decoder::{UiAccount, UiAccountEncoding};
+use solana_client::{rpc_client::RpcClient, rpc_response::RpcKeyedAccount};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+use crate::utils::deserialize_anchor_account;
+use crate::utils::get_tick_array_bitmap;
+use crate::utils::get_tick_array_key;
+use crate::utils::x64_to_price;
+use crate::{
+    config::Config,
+    error::{ClientError, Result},
+    price::sqrt_price_x64_to_price,
+    utils::{price_inverted, quoted_price_to_sqrt_price_x64, sqrt_price_x64_to_quoted_price},
+};
+
+/// Maximum number of initialized tick arrays loaded in each direction from the current one.
+const MAX_TICK_ARRAY_CROSSINGS: usize = 5;
+
+/// Result of simulating a swap against a snapshot of the pool.
+///
+/// Amounts are pool-side amounts: Token-2022 transfer fees are not included.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapQuote {
+    pub zero_for_one: bool,
+    pub is_base_input: bool,
+    /// Total amount paid into the pool, trading fee included.
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// Trading fee paid, denominated in the input token.
+    pub fee_amount: u64,
+    pub sqrt_price_x64_before: u128,
+    pub sqrt_price_x64_after: u128,
+    pub tick_after: i32,
+    /// Price of token_0 in token_1 before and after the swap, adjusted for decimals, or
+    /// the inverse when [`quote`] is given a quote mint of token_0.
+    pub price_before: String,
+    pub price_after: String,
+    /// Relative difference between the spot price and the execution price, fees excluded.
+    pub price_impact: f64,
+    /// Start indexes of every tick array the swap touches, in crossing order.
+    pub tick_array_start_indexes: VecDeque<i32>,
+}
+
+/// The accounts needed to quote a swap without talking to an RPC node.
+pub struct QuoteAccounts {
+    pub pool_pubkey: Pubkey,
+    pub amm_config: AmmConfig,
+    pub pool_state: PoolState,
+    pub tickarray_bitmap_extension: TickArrayBitmapExtension,
+    pub tick_arrays: HashMap<i32, TickArrayState>,
+}
+
+impl QuoteAccounts {
+    /// Builds the snapshot from raw accounts, telling them apart by their anchor discriminator.
+    pub fn from_accounts(accounts: &[(Pubkey, Account)]) -> Result<Self> {
+        let mut pool = None;
+        let mut amm_config = None;
+        let mut tickarray_bitmap_extension = None;
+        let mut tick_arrays = HashMap::new();
+        for (pubkey, account) in accounts {
+            if account.data.len() < 8 {
+                continue;
+            }
+            let discriminator = &account.data[..8];
+            if discriminator == PoolState::DISCRIMINATOR {
+                pool = Some((*pubkey, deserialize_anchor_account::<PoolState>(account)?));
+            } else if discriminator == AmmConfig::DISCRIMINATOR {
+                amm_config = Some(deserialize_anchor_account::<AmmConfig>(account)?);
+            } else if discriminator == TickArrayBitmapExtension::DISCRIMINATOR {
+                tickarray_bitmap_extension = Some(deserialize_anchor_account::<
+                    TickArrayBitmapExtension,
+                >(account)?);
+            } else if discriminator == TickArrayState::DISCRIMINATOR {
+                let tick_array = deserialize_anchor_account::<TickArrayState>(account)?;
+                tick_arrays.insert(tick_array.start_tick_index, tick_array);
+            }
+        }
+        let (pool_pubkey, pool_state) =
+            pool.ok_or_else(|| ClientError::Swap("pool account is missing".to_string()))?;
+        Ok(Self {
+            pool_pubkey,
+            amm_config: amm_config
+                .ok_or_else(|| ClientError::Swap("amm config account is missing".to_string()))?,
+            pool_state,
+            tickarray_bitmap_extension: tickarray_bitmap_extension.ok_or_else(|| {
+                ClientError::Swap("tick array bitmap extension account is missing".to_string())
+            })?,
+            tick_arrays,
+        })
+    }
+
+    /// Simulates a swap of `amount_specified` across the loaded tick arrays.
+    pub fn quote(
+        &self,
+        zero_for_one: bool,
+        is_base_input: bool,
+        amount_specified: u64,
+        sqrt_price_limit_x64: Option<u128>,
+    ) -> Result<SwapQuote> {
+        let pool_state = &self.pool_state;
+        let (is_pool_current_tick_array, current_valid_tick_array_start_index) = pool_state
+            .get_first_initialized_tick_array(
+                &Some(self.tickarray_bitmap_extension),
+                zero_for_one,
+            )?;
+        let result = swap_compute(
+            zero_for_one,
+            is_base_input,
+            is_pool_current_tick_array,
+            self.amm_config.trade_fee_rate,
+            amount_specified,
+            current_valid_tick_array_start_index,
+            sqrt_price_limit_x64.unwrap_or(0),
+            pool_state,
+            &self.tickarray_bitmap_extension,
+            &self.tick_arrays,
+        )?;
+
+        let decimals_0 = pool_state.mint_decimals_0;
+        let decimals_1 = pool_state.mint_decimals_1;
+        let sqrt_price_x64_before = pool_state.sqrt_price_x64;
+        let spot_price = x64_to_price(sqrt_price_x64_before).powi(2);
+        let amount_in_without_fee = result.amount_in.saturating_sub(result.fee_amount);
+        let price_impact = if amount_in_without_fee == 0 || spot_price == 0.0 {
+            0.0
+        } else {
+            let execution_price = result.amount_out as f64 / amount_in_without_fee as f64;
+            let spot_price = if zero_for_one {
+                spot_price
+            } else {
+                1.0 / spot_price
+            };
+            (1.0 - execution_price / spot_price).max(0.0)
+        };
+
+        Ok(SwapQuote {
+            zero_for_one,
+            is_base_input,
+            amount_in: result.amount_in,
+            amount_out: result.amount_out,
+            fee_amount: result.fee_amount,
+            sqrt_price_x64_before,
+            sqrt_price_x64_after: result.sqrt_price_x64,
+            tick_after: result.tick,
+            price_before: sqrt_price_x64_to_price(sqrt_price_x64_before, decimals_0, decimals_1),
+            price_after: sqrt_price_x64_to_price(result.sqrt_price_x64, decimals_0, decimals_1),
+            price_impact,
+            tick_array_start_indexes: result.tick_array_start_indexes,
+        })
+    }
+
+    /// Removes `liquidity` provided between two ticks from the snapshot, so that swaps
+    /// are quoted as if executed after withdrawing it.
+    pub fn remove_liquidity(
+        &mut self,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+        liquidity: u128,
+    ) {
+        let pool_state = &mut self.pool_state;
+        if pool_state.tick_current >= tick_lower_index && pool_state.tick_current < tick_upper_index
+        {
+            pool_state.liquidity = pool_state.liquidity.saturating_sub(liquidity);
+        }
+        // the position added its liquidity at the lower tick and removed it at the upper one
+        let tick_spacing = pool_state.tick_spacing;
+        for (tick_index, liquidity_net) in [
+            (tick_lower_index, -(liquidity as i128)),
+            (tick_upper_index, liquidity as i128),
+        ] {
+            let start_index =
+                TickArrayState::get_array_start_index(tick_index, tick_spacing.into());
+            if let Some(tick_array) = self.tick_arrays.get_mut(&start_index) {
+                let offset = ((tick_index - start_index) / i32::from(tick_spacing)) as usize;
+                let tick_state = &mut tick_array.ticks[offset];
+                tick_state.liquidity_net = tick_state.liquidity_net.saturating_add(liquidity_net);
+                tick_state.liquidity_gross = tick_state.liquidity_gross.saturating_sub(liquidity);
+            }
+        }
+    }
+}
+
+/// Fetches the pool, its amm config, the bitmap extension and the initialized tick
+/// arrays around `tick_current` in both swap directions.
+pub fn fetch_quote_accounts(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    pool_pubkey: Pubkey,
+) -> Result<Vec<(Pubkey, Account)>> {
+    let pool_account = rpc_client.get_account(&pool_pubkey)?;
+    let pool_state = deserialize_anchor_account::<PoolState>(&pool_account)?;
+
+    let amm_config = pool_state.amm_config;
+    let tickarray_bitmap_extension_key = get_tick_array_bitmap(
+        &amm_config,
+        &pool_state.token_mint_0,
+        &pool_state.token_mint_1,
+        program_id,
+    );
+    let load_accounts = vec![amm_config, tickarray_bitmap_extension_key];
+    let rsps = rpc_client.get_multiple_accounts(&load_accounts)?;
+    let amm_config_account = rsps[0]
+        .clone()
+        .ok_or(ClientError::AccountNotFound(amm_config))?;
+    let tickarray_bitmap_extension_account = rsps[1]
+        .clone()
+        .ok_or(ClientError::AccountNotFound(tickarray_bitmap_extension_key))?;
+    let tickarray_bitmap_extension = Some(deserialize_anchor_account::<TickArrayBitmapExtension>(
+        &tickarray_bitmap_extension_account,
+    )?);
+
+    let mut tick_array_start_indexes = Vec::new();
+    for zero_for_one in [true, false] {
+        let (_, mut current_valid_tick_array_start_index) = pool_state
+            .get_first_initialized_tick_array(&tickarray_bitmap_extension, zero_for_one)?;
+        if !tick_array_start_indexes.contains(&current_valid_tick_array_start_index) {
+            tick_array_start_indexes.push(current_valid_tick_array_start_index);
+        }
+        for _ in 0..MAX_TICK_ARRAY_CROSSINGS {
+            match pool_state.next_initialized_tick_array_start_index(
+                &tickarray_bitmap_extension,
+                current_valid_tick_array_start_index,
+                zero_for_one,
+            )? {
+                None => break,
+                Some(index) => {
+                    current_valid_tick_array_start_index = index;
+                    tick_array_start_indexes.push(index);
+                }
+            }
+        }
+    }
+    let tick_array_keys: Vec<Pubkey> = tick_array_start_indexes
+        .into_iter()
+        .map(|index| get_tick_array_key(&pool_pubkey, index, program_id))
+        .collect();
+    let rsps = rpc_client.get_multiple_accounts(&tick_array_keys)?;
+
+    let mut accounts = vec![
+        (pool_pubkey, pool_account),
+        (amm_config, amm_config_account),
+        (
+            tickarray_bitmap_extension_key,
+            tickarray_bitmap_extension_account,
+        ),
+    ];
+    for (key, rsp) in tick_array_keys.into_iter().zip(rsps) {
+        if let Some(account) = rsp {
+            accounts.push((key, account));
+        }
+    }
+    Ok(accounts)
+}
+
+/// Writes every account as `<pubkey>.json`, in the same format as `solana account --output json`.
+pub fn save_accounts_to_dir<P: AsRef<Path>>(dir: P, accounts: &[(Pubkey, Account)]) -> Result<()> {
+    fs::create_dir_all(&dir)?;
+    for (pubkey, account) in accounts {
+        let keyed_account = RpcKeyedAccount {
+            pubkey: pubkey.to_string(),
+            account: UiAccount::encode(pubkey, account, UiAccountEncoding::Base64, None, None),
+        };
+        let path = dir.as_ref().join(format!("{}.json", pubkey));
+        let content = serde_json::to_string_pretty(&keyed_account)
+            .map_err(|e| ClientError::Deserialization(format!("account {}: {}", pubkey, e)))?;
+        fs::write(path, content)?;
+    }
+    Ok(())
+}
+
+/// Reads every `*.json` account file written by `save_accounts_to_dir` or `solana account --output json`.
+pub fn load_accounts_from_dir<P: AsRef<Path>>(dir: P) -> Result<Vec<(Pubkey, Account)>> {
+    let mut accounts = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().map_or(true, |ext| ext != "json") {
+            continue;
+        }
+        let keyed_account: RpcKeyedAccount = serde_json::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| ClientError::Deserialization(format!("{}: {}", path.display(), e)))?;
+        let pubkey = keyed_account.pubkey.parse::<Pubkey>().map_err(|e| {
+            ClientError::Deserialization(format!("pubkey in {}: {}", path.display(), e))
+        })?;
+        let account = keyed_account.account.decode::<Account>().ok_or_else(|| {
+            ClientError::Deserialization(format!("account data in {}", path.display()))
+        })?;
+        accounts.push((pubkey, account));
+    }
+    Ok(accounts)
+}
+
+/// Converts a limit price quoted in `quote_mint`, token_1 by default, to the sqrt price
+/// limit of a swap in `pool`.
+pub fn sqrt_price_limit_x64(
+    pool: &PoolState,
+    limit_price: &str,
+    quote_mint: Option<&Pubkey>,
+) -> Result<u128> {
+    let inverted = price_inverted(&pool.token_mint_0, &pool.token_mint_1, quote_mint)?;
+    quoted_price_to_sqrt_price_x64(
+        limit_price,
+        pool.mint_decimals_0,
+        pool.mint_decimals_1,
+        inverted,
+    )
+}
+
+/// Quotes a swap against a pool, either from the live cluster or from an account snapshot on disk.
+///
+/// The limit price and the prices of the quote are in `quote_mint`, token_1 by default.
+#[allow(clippy::too_many_arguments)]
+pub fn quote(
+    config: &Config,
+    pool_pubkey: Pubkey,
+    input_mint: Pubkey,
+    amount: u64,
+    is_base_input: bool,
+    limit_price: Option<&str>,
+    quote_mint: Option<&Pubkey>,
+    accounts_dir: Option<&Path>,
+    save_dir: Option<&Path>,
+) -> Result<SwapQuote> {
+    let accounts = if let Some(accounts_dir) = accounts_dir {
+        load_accounts_from_dir(accounts_dir)?
+    } else {
+        let rpc_client = config.rpc_client()?;
+        let program_id = config.raydium_v3_program_id()?;
+        fetch_quote_accounts(&rpc_client, &program_id, pool_pubkey)?
+    };
+    if let Some(save_dir) = save_dir {
+        save_accounts_to_dir(save_dir, &accounts)?;
+    }
+
+    let quote_accounts = QuoteAccounts::from_accounts(&accounts)?;
+    if quote_accounts.pool_pubkey != pool_pubkey {
+        return Err(ClientError::InvalidArgument(format!(
+            "loaded pool {} does not match {}",
+            quote_accounts.pool_pubkey, pool_pubkey
+        )));
+    }
+    let pool = &quote_accounts.pool_state;
+    let zero_for_one = if input_mint == pool.token_mint_0 {
+        true
+    } else if input_mint == pool.token_mint_1 {
+        false
+    } else {
+        return Err(ClientError::InvalidArgument(format!(
+            "mint {} does not belong to pool {}",
+            input_mint, pool_pubkey
+        )));
+    };
+    let sqrt_price_limit_x64 = limit_price
+        .map(|price| sqrt_price_limit_x64(pool, price, quote_mint))
+        .transpose()?;
+
+    let mut quote =
+        quote_accounts.quote(zero_for_one, is_base_input, amount, sqrt_price_limit_x64)?;
+    if price_inverted(&pool.token_mint_0, &pool.token_mint_1, quote_mint)? {
+        let decimals_0 = pool.mint_decimals_0;
+        let decimals_1 = pool.mint_decimals_1;
+        quote.price_before = sqrt_price_x64_to_quoted_price(
+            quote.sqrt_price_x64_before,
+            decimals_0,
+            decimals_1,
+            true,
+        )?;
+        quote.price_after = sqrt_price_x64_to_quoted_price(
+            quote.sqrt_price_x64_after,
+            decimals_0,
+            decimals_1,
+            true,
+        )?;
+    }
+    Ok(quote)
+}
+
+struct SwapResult {
+    amount_in: u64,
+    amount_out: u64,
+    fee_amount: u64,
+    sqrt_price_x64: u128,
+    tick: i32,
+    tick_array_start_indexes: VecDeque<i32>,
+}
+
+#[derive(Debug)]
+struct SwapState {
+    amount_specified_remaining: u64,
+    amount_calculated: u64,
+    fee_amount: u64,
+    sqrt_price_x64: u128,
+    tick: i32,
+    liquidity: u128,
+}
+
+#[derive(Default)]
+struct StepComputations {
+    sqrt_price_start_x64: u128,
+    tick_next: i32,
+    initialized: bool,
+    sqrt_price_next_x64: u128,
+    amount_in: u64,
+    amount_out: u64,
+    fee_amount: u64,
+}
+
+fn swap_compute(
+    zero_for_one: bool,
+    is_base_input: bool,
+    is_pool_current_tick_array: bool,
+    fee: u32,
+    amount_specified: u64,
+    current_valid_tick_array_start_index: i32,
+    sqrt_price_limit_x64: u128,
+    pool_state: &PoolState,
+    tickarray_bitmap_extension: &TickArrayBitmapExtension,
+    tick_arrays: &HashMap<i32, TickArrayState>,
+) -> Result<SwapResult> {
+    if amount_specified == 0 {
+        return Err(ClientError::Swap(
+            "amount_specified must not be 0".to_string(),
+        ));
+    }
+    let sqrt_price_limit_x64 = if sqrt_price_limit_x64 == 0 {
+        if zero_for_one {
+            tick_math::MIN_SQRT_PRICE_X64 + 1
+        } else {
+            tick_math::MAX_SQRT_PRICE_X64 - 1
+        }
+    } else {
+        sqrt_price_limit_x64
+    };
+    if zero_for_one {
+        if sqrt_price_limit_x64 < tick_math::MIN_SQRT_PRICE_X64 {
+            return Err(ClientError::Swap(
+                "sqrt_price_limit_x64 must be greater than MIN_SQRT_PRICE_X64".to_string(),
+            ));
+        }
+        if sqrt_price_limit_x64 >= pool_state.sqrt_price_x64 {
+            return Err(ClientError::Swap(
+                "sqrt_price_limit_x64 must be smaller than current".to_string(),
+            ));
+        }
+    } else {
+        if sqrt_price_limit_x64 > tick_math::MAX_SQRT_PRICE_X64 {
+            return Err(ClientError::Swap(
+                "sqrt_price_limit_x64 must be smaller than MAX_SQRT_PRICE_X64".to_string(),
+            ));
+        }
+        if sqrt_price_limit_x64 <= pool_state.sqrt_price_x64 {
+            return Err(ClientError::Swap(
+                "sqrt_price_limit_x64 must be greater than current".to_string(),
+            ));
+        }
+    }
+    let load_tick_array = |start_index: i32| {
+        tick_arrays.get(&start_index).copied().ok_or_else(|| {
+            ClientError::Swap(format!(
+                "tick array {} is not loaded, the swap crosses too many tick arrays",
+                start_index
+            ))
+        })
+    };
+    let mut tick_match_current_tick_array = is_pool_current_tick_array;
+
+    let mut state = SwapState {
+        amount_specified_remaining: amount_specified,
+        amount_calculated: 0,
+        fee_amount: 0,
+        sqrt_price_x64: pool_state.sqrt_price_x64,
+        tick: pool_state.tick_current,
+        liquidity: pool_state.liquidity,
+    };
+
+    let mut current_valid_tick_array_start_index = current_valid_tick_array_start_index;
+    let mut tick_array_current = load_tick_array(current_valid_tick_array_start_index)?;
+    let mut tick_array_start_index_vec = VecDeque::new();
+    tick_array_start_index_vec.push_back(current_valid_tick_array_start_index);
+    // loop across ticks until input liquidity is consumed, or the limit price is reached
+    while state.amount_specified_remaining != 0
+        && state.sqrt_price_x64 != sqrt_price_limit_x64
+        && state.tick < tick_math::MAX_TICK
+        && state.tick > tick_math::MIN_TICK
+    {
+        let mut step = StepComputations {
+            sqrt_price_start_x64: state.sqrt_price_x64,
+            ..Default::default()
+        };
+
+        let mut next_initialized_tick = if let Some(tick_state) = tick_array_current
+            .next_initialized_tick(state.tick, pool_state.tick_spacing, zero_for_one)?
+        {
+            *tick_state
+        } else if !tick_match_current_tick_array {
+            tick_match_current_tick_array = true;
+            *tick_array_current.first_initialized_tick(zero_for_one)?
+        } else {
+            TickState::default()
+        };
+        if !next_initialized_tick.is_initialized() {
+            current_valid_tick_array_start_index = pool_state
+                .next_initialized_tick_array_start_index(
+                    &Some(*tickarray_bitmap_extension),
+                    current_valid_tick_array_start_index,
+                    zero_for_one,
+                )?
+                .ok_or_else(|| {
+                    ClientError::Swap("tick array start tick index out of range limit".to_string())
+                })?;
+            tick_array_current = load_tick_array(current_valid_tick_array_start_index)?;
+            tick_array_start_index_vec.push_back(current_valid_tick_array_start_index);
+            next_initialized_tick = *tick_array_current.first_initialized_tick(zero_for_one)?;
+        }
+        step.tick_next = next_initialized_tick
+            .tick
+            .clamp(tick_math::MIN_TICK, tick_math::MAX_TICK);
+        step.initialized = next_initialized_tick.is_initialized();
+        step.sqrt_price_next_x64 = tick_math::get_sqrt_price_at_tick(step.tick_next)?;
+
+        let target_price = if (zero_for_one && step.sqrt_price_next_x64 < sqrt_price_limit_x64)
+            || (!zero_for_one && step.sqrt_price_next_x64 > sqrt_price_limit_x64)
+        {
+            sqrt_price_limit_x64
+        } else {
+            step.sqrt_price_next_x64
+        };
+        let swap_step = swap_math::compute_swap_step(
+            state.sqrt_price_x64,
+            target_price,
+            state.liquidity,
+            state.amount_specified_remaining,
+            fee,
+            is_base_input,
+            zero_for_one,
+            1,
+        )?;
+        state.sqrt_price_x64 = swap_step.sqrt_price_next_x64;
+        step.amount_in = swap_step.amount_in;
+        step.amount_out = swap_step.amount_out;
+        step.fee_amount = swap_step.fee_amount;
+
+        if is_base_input {
+            state.amount_specified_remaining = state
+                .amount_specified_remaining
+                .checked_sub(step.amount_in + step.fee_amount)
+                .ok_or_else(|| {
+                    ClientError::MathOverflow("amount_specified_remaining".to_string())
+                })?;
+            state.amount_calculated = state
+                .amount_calculated
+                .checked_add(step.amount_out)
+                .ok_or_else(|| ClientError::MathOverflow("amount_calculated".to_string()))?;
+        } else {
+            state.amount_specified_remaining = state
+                .amount_specified_remaining
+                .checked_sub(step.amount_out)
+                .ok_or_else(|| {
+                    ClientError::MathOverflow("amount_specified_remaining".to_string())
+                })?;
+            state.amount_calculated = state
+                .amount_calculated
+                .checked_add(step.amount_in + step.fee_amount)
+                .ok_or_else(|| ClientError::MathOverflow("amount_calculated".to_string()))?;
+        }
+        state.fee_amount = state
+            .fee_amount
+            .checked_add(step.fee_amount)
+            .ok_or_else(|| ClientError::MathOverflow("fee_amount".to_string()))?;
+
+        if state.sqrt_price_x64 == step.sqrt_price_next_x64 {
+            // if the tick is initialized, run the tick transition
+            if step.initialized {
+                let mut liquidity_net = next_initialized_tick.liquidity_net;
+                if zero_for_one {
+                    liquidity_net = liquidity_net.neg();
+                }
+                state.liquidity = liquidity_math::add_delta(state.liquidity, liquidity_net)?;
+            }
+
+            state.tick = if zero_for_one {
+                step.tick_next - 1
+            } else {
+                step.tick_next
+            };
+        } else if state.sqrt_price_x64 != step.sqrt_price_start_x64 {
+            // recompute unless we're on a lower tick boundary (i.e. already transitioned ticks), and haven't moved
+            state.tick = tick_math::get_tick_at_sqrt_price(state.sqrt_price_x64)?;
+        }
+    }
+
+    let amount_processed = amount_specified - state.amount_specified_remaining;
+    let (amount_in, amount_out) = if is_base_input {
+        (amount_processed, state.amount_calculated)
+    } else {
+        (state.amount_calculated, amount_processed)
+    };
+    Ok(SwapResult {
+        amount_in,
+        amount_out,
+        fee_amount: state.fee_amount,
+        sqrt_price_x64: state.sqrt_price_x64,
+        tick: state.tick,
+        tick_array_start_indexes: tick_array_start_index_vec,
+    })
+}
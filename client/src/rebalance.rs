@@ -0,0 +1,393 @@
+use anchor_client::{Client, Cluster};
+use anchor_lang::prelude::AccountMeta;
+use rand::rngs::OsRng;
+use raydium_amm_v3::libraries::liquidity_math;
+use raydium_amm_v3::libraries::tick_math;
+use raydium_amm_v3::states::TickArrayState;
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+use crate::create_token_account::create_missing_ata_instrs;
+use crate::decrease_liquidity::{
+    close_personal_position_instr, decrease_liquidity_instr, get_reward_mints,
+    reward_remaining_accounts,
+};
+use crate::increase_liquidity::{
+    liquidity_for_deposit, open_position_with_token22_nft_instr, DepositAmount,
+};
+use crate::quote::{fetch_quote_accounts, QuoteAccounts};
+use crate::swap::swap_instrs;
+use crate::utils::amount_with_slippage;
+use crate::utils::find_position;
+use crate::utils::get_tick_array_bitmap;
+use crate::utils::PoolMints;
+use crate::wsol::{is_native_mint, user_token_account, WsolAccount};
+use crate::{
+    config::Config,
+    error::{ClientError, Result},
+    rpc::send_txns,
+    utils::{read_keypair_file, PositionSelector},
+};
+
+/// Outcome of moving a position to a new range.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RebalanceResult {
+    pub token_mint_0: Pubkey,
+    pub token_mint_1: Pubkey,
+    pub mint_decimals_0: u8,
+    pub mint_decimals_1: u8,
+    /// NFT mint of the closed position.
+    pub old_nft_mint: Pubkey,
+    pub old_tick_lower_index: i32,
+    pub old_tick_upper_index: i32,
+    /// Liquidity of the closed position.
+    pub old_liquidity: u128,
+    /// NFT mint of the opened position.
+    pub nft_mint: Pubkey,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    /// Liquidity of the new position, at the price expected after the swap.
+    pub liquidity: u128,
+    /// Amounts expected from the closed position, after transfer fees.
+    pub withdrawn: (u64, u64),
+    /// Swap made between closing and opening the positions, if any.
+    pub swap: Option<RebalanceSwap>,
+    /// Amounts expected in the new position, before transfer fees.
+    pub deposited: (u64, u64),
+}
+
+/// Swap of the withdrawn tokens to the ratio of the new range.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RebalanceSwap {
+    /// Whether token_0 is swapped for token_1.
+    pub zero_for_one: bool,
+    pub amount_in: u64,
+    /// Least amount received, after slippage and transfer fees.
+    pub amount_out_min: u64,
+}
+
+/// Returns the swap bringing `amount_0` and `amount_1` to the ratio a position between
+/// two sqrt prices holds at `sqrt_price_x64`, as the direction (`true` for token_0 to
+/// token_1) and the input amount, or `None` when no swap is needed.
+///
+/// The amounts are valued at the current price, ignoring the trading fee and the price
+/// impact of the swap.
+pub fn swap_for_range(
+    amount_0: u64,
+    amount_1: u64,
+    sqrt_price_x64: u128,
+    sqrt_price_lower_x64: u128,
+    sqrt_price_upper_x64: u128,
+) -> Option<(bool, u64)> {
+    // below the range a position holds only token_0, above it only token_1
+    let (zero_for_one, amount_in) = if sqrt_price_x64 <= sqrt_price_lower_x64 {
+        (false, amount_1)
+    } else if sqrt_price_x64 >= sqrt_price_upper_x64 {
+        (true, amount_0)
+    } else {
+        let q64 = (1u128 << 64) as f64;
+        let sqrt_price = sqrt_price_x64 as f64 / q64;
+        let sqrt_price_lower = sqrt_price_lower_x64 as f64 / q64;
+        let sqrt_price_upper = sqrt_price_upper_x64 as f64 / q64;
+        let price = sqrt_price * sqrt_price;
+
+        // amounts held by a unit of liquidity
+        let unit_0 = 1.0 / sqrt_price - 1.0 / sqrt_price_upper;
+        let unit_1 = sqrt_price - sqrt_price_lower;
+        let value = amount_0 as f64 * price + amount_1 as f64;
+        let target_1 = value * unit_1 / (unit_0 * price + unit_1);
+        if target_1 > amount_1 as f64 {
+            let amount_in = ((target_1 - amount_1 as f64) / price) as u64;
+            (true, amount_in.min(amount_0))
+        } else {
+            let amount_in = (amount_1 as f64 - target_1) as u64;
+            (false, amount_in.min(amount_1))
+        }
+    };
+    (amount_in > 0).then_some((zero_for_one, amount_in))
+}
+
+/// Moves the position addressed by `position` to the range addressed by `new_range`:
+/// withdraws all its liquidity and closes it, swaps the proceeds to the ratio of the new
+/// range when `swap` is set, then opens a position with them.
+///
+/// The instructions are packed into as few transactions as fit. The new position is
+/// sized from the amounts received at worst after `slippage` and transfer fees, so any
+/// surplus stays in the payer's token accounts.
+pub fn rebalance(
+    config: &Config,
+    position: PositionSelector,
+    new_range: PositionSelector,
+    pool_pubkey: Pubkey,
+    swap: bool,
+    slippage: f64,
+) -> Result<RebalanceResult> {
+    let payer = read_keypair_file(&config.global.payer_path)?;
+
+    let url = Cluster::Custom(config.global.http_url.clone(), config.global.ws_url.clone());
+    let client = Client::new(url, &payer);
+    let rpc_client = config.rpc_client()?;
+
+    let program = client.program(config.raydium_v3_program_id()?)?;
+    let program_pubkey = program.id();
+
+    let accounts = fetch_quote_accounts(&rpc_client, &program_pubkey, pool_pubkey)?;
+    let mut quote_accounts = QuoteAccounts::from_accounts(&accounts)?;
+    let pool = quote_accounts.pool_state;
+    let mint0 = pool.token_mint_0;
+    let mint1 = pool.token_mint_1;
+
+    let (old_position, user_nft_token_info) = find_position(
+        &rpc_client,
+        &payer.pubkey(),
+        &program_pubkey,
+        &pool_pubkey,
+        &pool,
+        &position,
+    )?
+    .ok_or_else(|| {
+        ClientError::PositionNotFound(format!(
+            "no position in pool {} for {:?}",
+            pool_pubkey, position
+        ))
+    })?;
+    let old_tick_lower_index = old_position.tick_lower_index;
+    let old_tick_upper_index = old_position.tick_upper_index;
+    let (tick_lower_index, tick_upper_index) = new_range
+        .tick_indexes(&pool)?
+        .ok_or_else(|| ClientError::InvalidArgument("no tick range".to_string()))?;
+    if (tick_lower_index, tick_upper_index) == (old_tick_lower_index, old_tick_upper_index) {
+        return Err(ClientError::InvalidArgument(format!(
+            "the position already covers ticks {} to {}",
+            tick_lower_index, tick_upper_index
+        )));
+    }
+    let old_liquidity = old_position.liquidity;
+    if old_liquidity == 0 {
+        return Err(ClientError::InvalidArgument(format!(
+            "position {} holds no liquidity",
+            old_position.nft_mint
+        )));
+    }
+
+    // withdraw everything from the old position
+    let (amount_0, amount_1) = liquidity_math::get_delta_amounts_signed(
+        pool.tick_current,
+        pool.sqrt_price_x64,
+        old_tick_lower_index,
+        old_tick_upper_index,
+        -(old_liquidity as i128),
+    )?;
+    let amount_0_with_slippage = amount_with_slippage(amount_0, slippage, false)?;
+    let amount_1_with_slippage = amount_with_slippage(amount_1, slippage, false)?;
+    let mints = PoolMints::fetch(&rpc_client, mint0, mint1)?;
+    let transfer_fee = mints.transfer_fee(amount_0_with_slippage, amount_1_with_slippage)?;
+    let amount_0_min = amount_0_with_slippage
+        .checked_sub(transfer_fee.0.transfer_fee)
+        .ok_or_else(|| ClientError::MathOverflow("amount_0_min".to_string()))?;
+    let amount_1_min = amount_1_with_slippage
+        .checked_sub(transfer_fee.1.transfer_fee)
+        .ok_or_else(|| ClientError::MathOverflow("amount_1_min".to_string()))?;
+    let expected_fee = mints.transfer_fee(amount_0, amount_1)?;
+    let withdrawn = (
+        amount_0.saturating_sub(expected_fee.0.transfer_fee),
+        amount_1.saturating_sub(expected_fee.1.transfer_fee),
+    );
+
+    // native SOL is received in a temporary account, unwrapped once the position is open
+    let wsol = WsolAccount::for_mint(config, &mint0, 0)
+        .or_else(|| WsolAccount::for_mint(config, &mint1, 0));
+    let user_token_account_0 = user_token_account(
+        wsol.as_ref(),
+        &payer.pubkey(),
+        &mint0,
+        &transfer_fee.0.owner,
+    );
+    let user_token_account_1 = user_token_account(
+        wsol.as_ref(),
+        &payer.pubkey(),
+        &mint1,
+        &transfer_fee.1.owner,
+    );
+    let tickarray_bitmap_extension =
+        get_tick_array_bitmap(&pool.amm_config, &mint0, &mint1, &program_pubkey);
+
+    let sqrt_price_lower_x64 = tick_math::get_sqrt_price_at_tick(tick_lower_index)?;
+    let sqrt_price_upper_x64 = tick_math::get_sqrt_price_at_tick(tick_upper_index)?;
+
+    // swap the proceeds to the ratio of the new range, quoted without the old liquidity
+    quote_accounts.remove_liquidity(old_tick_lower_index, old_tick_upper_index, old_liquidity);
+    let swap_plan = if swap {
+        swap_for_range(
+            amount_0_min,
+            amount_1_min,
+            pool.sqrt_price_x64,
+            sqrt_price_lower_x64,
+            sqrt_price_upper_x64,
+        )
+    } else {
+        None
+    };
+    let mut holdings = (amount_0_min, amount_1_min);
+    let mut sqrt_price_x64 = pool.sqrt_price_x64;
+    let mut tick_current = pool.tick_current;
+    let mut swap_txn_instrs = Vec::new();
+    let mut rebalance_swap = None;
+    if let Some((zero_for_one, amount_in)) = swap_plan {
+        let quoted_swap = swap_instrs(
+            config,
+            &payer,
+            &quote_accounts,
+            &mints,
+            (user_token_account_0, user_token_account_1),
+            zero_for_one,
+            amount_in,
+            true,
+            None,
+            slippage,
+        )?;
+        let amount_out_min = quoted_swap.other_amount_threshold;
+        holdings = if zero_for_one {
+            (holdings.0 - amount_in, holdings.1 + amount_out_min)
+        } else {
+            (holdings.0 + amount_out_min, holdings.1 - amount_in)
+        };
+        sqrt_price_x64 = quoted_swap.quote.sqrt_price_x64_after;
+        tick_current = quoted_swap.quote.tick_after;
+        swap_txn_instrs = quoted_swap.instructions;
+        rebalance_swap = Some(RebalanceSwap {
+            zero_for_one,
+            amount_in,
+            amount_out_min,
+        });
+    }
+
+    // open the new position with what is held at worst, sized at the price after the swap
+    let deposit_fee = mints.transfer_fee(holdings.0, holdings.1)?;
+    let (liquidity, is_base_0) = liquidity_for_deposit(
+        DepositAmount::Both {
+            amount_0_max: holdings.0.saturating_sub(deposit_fee.0.transfer_fee),
+            amount_1_max: holdings.1.saturating_sub(deposit_fee.1.transfer_fee),
+        },
+        sqrt_price_x64,
+        sqrt_price_lower_x64,
+        sqrt_price_upper_x64,
+    );
+    if liquidity == 0 {
+        return Err(ClientError::InvalidArgument(
+            "the withdrawn amounts provide no liquidity in the new range".to_string(),
+        ));
+    }
+    let (deposit_0, deposit_1) = liquidity_math::get_delta_amounts_signed(
+        tick_current,
+        sqrt_price_x64,
+        tick_lower_index,
+        tick_upper_index,
+        liquidity as i128,
+    )?;
+
+    let reward_mints = get_reward_mints(&rpc_client, &pool)?;
+    let mut ata_mints: Vec<(Pubkey, Pubkey)> =
+        [(mint0, transfer_fee.0.owner), (mint1, transfer_fee.1.owner)]
+            .into_iter()
+            .filter(|(mint, _)| wsol.is_none() || !is_native_mint(mint))
+            .collect();
+    ata_mints.extend(reward_mints.iter().copied());
+    let mut setup_instrs =
+        create_missing_ata_instrs(&rpc_client, &payer.pubkey(), &payer.pubkey(), &ata_mints)?;
+    if let Some(wsol) = &wsol {
+        setup_instrs.extend(wsol.wrap_instrs(&rpc_client, &payer.pubkey(), &payer.pubkey())?);
+    }
+
+    let mut remaining_accounts = Vec::new();
+    remaining_accounts.push(AccountMeta::new(tickarray_bitmap_extension, false));
+    remaining_accounts.extend(reward_remaining_accounts(
+        &pool,
+        &payer.pubkey(),
+        &reward_mints,
+    ));
+    let mut withdraw_instrs = decrease_liquidity_instr(
+        config,
+        &payer,
+        pool_pubkey,
+        pool.token_vault_0,
+        pool.token_vault_1,
+        mint0,
+        mint1,
+        old_position.nft_mint,
+        user_nft_token_info.key,
+        user_token_account_0,
+        user_token_account_1,
+        remaining_accounts,
+        old_liquidity,
+        amount_0_min,
+        amount_1_min,
+        old_tick_lower_index,
+        old_tick_upper_index,
+        TickArrayState::get_array_start_index(old_tick_lower_index, pool.tick_spacing.into()),
+        TickArrayState::get_array_start_index(old_tick_upper_index, pool.tick_spacing.into()),
+    )?;
+    withdraw_instrs.extend(close_personal_position_instr(
+        config,
+        &payer,
+        old_position.nft_mint,
+        user_nft_token_info.key,
+        user_nft_token_info.program,
+    )?);
+
+    let nft_mint = Keypair::generate(&mut OsRng);
+    let mut open_instrs = open_position_with_token22_nft_instr(
+        config,
+        &payer,
+        pool_pubkey,
+        pool.token_vault_0,
+        pool.token_vault_1,
+        mint0,
+        mint1,
+        nft_mint.pubkey(),
+        payer.pubkey(),
+        user_token_account_0,
+        user_token_account_1,
+        vec![AccountMeta::new(tickarray_bitmap_extension, false)],
+        0,
+        holdings.0,
+        holdings.1,
+        tick_lower_index,
+        tick_upper_index,
+        TickArrayState::get_array_start_index(tick_lower_index, pool.tick_spacing.into()),
+        TickArrayState::get_array_start_index(tick_upper_index, pool.tick_spacing.into()),
+        false,
+        Some(is_base_0),
+    )?;
+    if let Some(wsol) = &wsol {
+        open_instrs.push(wsol.unwrap_instr(&payer.pubkey())?);
+    }
+
+    // send
+    let mut signers = vec![&payer, &nft_mint];
+    signers.extend(wsol.as_ref().map(|wsol| &wsol.keypair));
+    send_txns(
+        config,
+        &rpc_client,
+        vec![setup_instrs, withdraw_instrs, swap_txn_instrs, open_instrs],
+        &payer,
+        &signers,
+    )?;
+
+    Ok(RebalanceResult {
+        token_mint_0: mint0,
+        token_mint_1: mint1,
+        mint_decimals_0: pool.mint_decimals_0,
+        mint_decimals_1: pool.mint_decimals_1,
+        old_nft_mint: old_position.nft_mint,
+        old_tick_lower_index,
+        old_tick_upper_index,
+        old_liquidity,
+        nft_mint: nft_mint.pubkey(),
+        tick_lower_index,
+        tick_upper_index,
+        liquidity,
+        withdrawn,
+        swap: rebalance_swap,
+        deposited: (deposit_0, deposit_1),
+    })
+}
@@ -1,20 +1,634 @@
-use anyhow::Result;
-use solana_client::{rpc_client::RpcClient, rpc_config::RpcSendTransactionConfig};
+use std::fmt;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use raydium_amm_v3::error::ErrorCode as RaydiumErrorCode;
+use solana_account_decoder::UiAccountEncoding;
+
+use solana_client::{
+    client_error::ClientErrorKind,
+    rpc_client::RpcClient,
+    rpc_config::{
+        RpcSendTransactionConfig, RpcSimulateTransactionAccountsConfig,
+        RpcSimulateTransactionConfig, RpcTransactionConfig,
+    },
+    rpc_request::RpcError,
+};
 use solana_sdk::{
-    commitment_config::CommitmentConfig, signature::Signature, transaction::Transaction,
+    account::Account,
+    commitment_config::CommitmentConfig,
+    compute_budget::{self, ComputeBudgetInstruction},
+    instruction::{Instruction, InstructionError},
+    message::Message,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::Transaction,
+    transaction::TransactionError,
 };
+use solana_transaction_status::{TransactionStatus, UiTransactionEncoding};
+use spl_token_2022::extension::StateWithExtensions;
+
+use crate::{
+    config::Config,
+    error::{ClientError, Result},
+};
+
+/// Maximum compute units a transaction can request.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Builds and signs a transaction paid by `payer`, prepending the compute budget
+/// instructions from the `priority_fee` configuration.
+///
+/// Without a configured `compute_unit_limit`, the limit is the compute units used by a
+/// simulation plus `compute_unit_margin`, or `MAX_COMPUTE_UNIT_LIMIT` when the
+/// simulation fails.
+pub fn build_txn(
+    config: &Config,
+    client: &RpcClient,
+    instructions: &[Instruction],
+    payer: &Keypair,
+    signers: &[&Keypair],
+) -> Result<Transaction> {
+    let compute_unit_limit = match config.priority_fee.compute_unit_limit {
+        Some(compute_unit_limit) => compute_unit_limit,
+        None => {
+            let simulated = budgeted_instructions(config, instructions, MAX_COMPUTE_UNIT_LIMIT);
+            let txn = Transaction::new_with_payer(&simulated, Some(&payer.pubkey()));
+            let result = client
+                .simulate_transaction_with_config(
+                    &txn,
+                    RpcSimulateTransactionConfig {
+                        sig_verify: false,
+                        replace_recent_blockhash: true,
+                        commitment: Some(client.commitment()),
+                        ..RpcSimulateTransactionConfig::default()
+                    },
+                )?
+                .value;
+            simulated_compute_unit_limit(config, result.err.is_none(), result.units_consumed)
+        }
+    };
+    sign_txn(
+        config,
+        client,
+        &budgeted_instructions(config, instructions, compute_unit_limit),
+        payer,
+        signers,
+    )
+}
+
+/// Prepends the compute budget instructions to `instructions`, replacing any they hold.
+fn budgeted_instructions(
+    config: &Config,
+    instructions: &[Instruction],
+    compute_unit_limit: u32,
+) -> Vec<Instruction> {
+    let mut budgeted = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+        compute_unit_limit,
+    )];
+    if config.priority_fee.compute_unit_price > 0 {
+        budgeted.push(ComputeBudgetInstruction::set_compute_unit_price(
+            config.priority_fee.compute_unit_price,
+        ));
+    }
+    // Compute budget instructions are owned by the builder.
+    budgeted.extend(
+        instructions
+            .iter()
+            .filter(|instruction| instruction.program_id != compute_budget::id())
+            .cloned(),
+    );
+    budgeted
+}
+
+/// Compute unit limit derived from a simulation: the units consumed plus
+/// `compute_unit_margin`, or `MAX_COMPUTE_UNIT_LIMIT` when the simulation failed.
+fn simulated_compute_unit_limit(
+    config: &Config,
+    succeeded: bool,
+    units_consumed: Option<u64>,
+) -> u32 {
+    match units_consumed {
+        Some(units_consumed) if succeeded => {
+            let with_margin =
+                (units_consumed as f64 * (1.0 + config.priority_fee.compute_unit_margin)).ceil();
+            (with_margin as u64).min(MAX_COMPUTE_UNIT_LIMIT.into()) as u32
+        }
+        _ => MAX_COMPUTE_UNIT_LIMIT,
+    }
+}
+
+/// Signs the instructions with a blockhash fetched at the configured commitment.
+fn sign_txn(
+    config: &Config,
+    client: &RpcClient,
+    instructions: &[Instruction],
+    payer: &Keypair,
+    signers: &[&Keypair],
+) -> Result<Transaction> {
+    let (recent_hash, _) = client.get_latest_blockhash_with_commitment(config.commitment()?)?;
+    Ok(Transaction::new_signed_with_payer(
+        instructions,
+        Some(&payer.pubkey()),
+        signers,
+        recent_hash,
+    ))
+}
 
-pub fn send_txn(client: &RpcClient, txn: &Transaction, wait_confirm: bool) -> Result<Signature> {
-    Ok(client.send_and_confirm_transaction_with_spinner_and_config(
+/// Number of times a transaction is signed, with a fresh blockhash each time, before
+/// giving up on it expiring.
+const MAX_SIGN_ATTEMPTS: usize = 3;
+/// Time after signing during which RPC errors while checking the blockhash are retried.
+/// A blockhash expires after 150 blocks, about a minute.
+const BLOCKHASH_CHECK_TIMEOUT: Duration = Duration::from_secs(120);
+/// Time between two broadcasts of the same transaction.
+const REBROADCAST_INTERVAL: Duration = Duration::from_secs(2);
+/// Time between two signature status polls.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Number of attempts at fetching the details of a confirmed transaction.
+const MAX_FETCH_ATTEMPTS: usize = 10;
+
+/// Outcome of a transaction sent (or simulated) by `send_txn`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxnResult {
+    pub signature: Signature,
+    pub slot: u64,
+    /// Fee paid in lamports, `None` when the transaction details could not be fetched.
+    pub fee: Option<u64>,
+    pub compute_units_consumed: Option<u64>,
+}
+
+impl fmt::Display for TxnResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (slot {}", self.signature, self.slot)?;
+        if let Some(fee) = self.fee {
+            write!(f, ", fee {} lamports", fee)?;
+        }
+        if let Some(compute_units_consumed) = self.compute_units_consumed {
+            write!(f, ", {} compute units", compute_units_consumed)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Whether an RPC error may go away by sending again.
+pub fn is_retriable_error(err: &solana_client::client_error::ClientError) -> bool {
+    match err.kind() {
+        ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) => true,
+        ClientErrorKind::RpcError(RpcError::RpcRequestError(_)) => true,
+        // Node is behind or unhealthy.
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => {
+            *code == -32005 || *code == -32004
+        }
+        ClientErrorKind::TransactionError(TransactionError::BlockhashNotFound) => true,
+        _ => false,
+    }
+}
+
+/// Builds, signs and sends the instructions, rebroadcasting until the transaction reaches
+/// the configured commitment.
+///
+/// When the blockhash expires before that, the transaction is re-signed with a fresh one,
+/// signing at most `MAX_SIGN_ATTEMPTS` times. Retriable RPC errors are retried, for up to
+/// `BLOCKHASH_CHECK_TIMEOUT` when checking the blockhash; other errors and failed
+/// transactions are returned at once. With `dry_run` set, the transaction is
+/// only simulated, once, and the report printed.
+pub fn send_txn(
+    config: &Config,
+    client: &RpcClient,
+    instructions: &[Instruction],
+    payer: &Keypair,
+    signers: &[&Keypair],
+) -> Result<TxnResult> {
+    let commitment = config.commitment()?;
+    if config.global.dry_run {
+        // A single simulation gives both the report and the compute unit limit.
+        let compute_unit_limit = config.priority_fee.compute_unit_limit;
+        let txn = sign_txn(
+            config,
+            client,
+            &budgeted_instructions(
+                config,
+                instructions,
+                compute_unit_limit.unwrap_or(MAX_COMPUTE_UNIT_LIMIT),
+            ),
+            payer,
+            signers,
+        )?;
+        let report = simulate_txn(client, &txn)?;
+        print_simulation_report(&report);
+        if let Some(error) = report.error {
+            return Err(ClientError::Simulation(error));
+        }
+        let compute_unit_limit = compute_unit_limit
+            .unwrap_or_else(|| simulated_compute_unit_limit(config, true, report.units_consumed));
+        let mut message = Message::new(
+            &budgeted_instructions(config, instructions, compute_unit_limit),
+            Some(&payer.pubkey()),
+        );
+        message.recent_blockhash = txn.message.recent_blockhash;
+        return Ok(TxnResult {
+            signature: txn.signatures[0],
+            slot: report.slot,
+            fee: client.get_fee_for_message(&message).ok(),
+            compute_units_consumed: report.units_consumed,
+        });
+    }
+
+    for _ in 0..MAX_SIGN_ATTEMPTS {
+        let txn = build_txn(config, client, instructions, payer, signers)?;
+        let signature = txn.signatures[0];
+        let blockhash_check_deadline = Instant::now() + BLOCKHASH_CHECK_TIMEOUT;
+        loop {
+            if let Err(err) = client.send_transaction_with_config(
+                &txn,
+                RpcSendTransactionConfig {
+                    skip_preflight: true,
+                    preflight_commitment: Some(commitment.commitment),
+                    max_retries: Some(0),
+                    ..RpcSendTransactionConfig::default()
+                },
+            ) {
+                if !is_retriable_error(&err) {
+                    return Err(err.into());
+                }
+            }
+
+            let rebroadcast_at = Instant::now() + REBROADCAST_INTERVAL;
+            while Instant::now() < rebroadcast_at {
+                sleep(POLL_INTERVAL);
+                let Some(status) = get_signature_status(client, &signature)? else {
+                    continue;
+                };
+                if let Some(err) = status.err {
+                    let logs = get_txn_details(client, &signature)
+                        .map(|details| details.logs)
+                        .unwrap_or_default();
+                    return Err(ClientError::TransactionFailed {
+                        signature,
+                        reason: decode_transaction_error(&err, &logs),
+                    });
+                }
+                if status.satisfies_commitment(commitment) {
+                    let details = get_txn_details(client, &signature);
+                    return Ok(TxnResult {
+                        signature,
+                        slot: status.slot,
+                        fee: details.as_ref().map(|details| details.fee),
+                        compute_units_consumed: details
+                            .and_then(|details| details.compute_units_consumed),
+                    });
+                }
+            }
+
+            match client.is_blockhash_valid(&txn.message.recent_blockhash, commitment) {
+                Ok(true) => continue,
+                // Only re-sign once the expired transaction can no longer land, so that it
+                // is never executed twice.
+                Ok(false) if get_signature_status(client, &signature)?.is_none() => break,
+                Ok(false) => continue,
+                Err(err)
+                    if is_retriable_error(&err) && Instant::now() < blockhash_check_deadline =>
+                {
+                    continue
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+    Err(ClientError::TransactionExpired(MAX_SIGN_ATTEMPTS))
+}
+
+/// Serialized size of a legacy transaction paid by `payer` holding the instructions and
+/// both compute budget instructions added by `build_txn`.
+pub fn txn_size(instructions: &[Instruction], payer: &Pubkey) -> usize {
+    let mut budgeted = vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT),
+        ComputeBudgetInstruction::set_compute_unit_price(1),
+    ];
+    budgeted.extend(instructions.iter().cloned());
+    let message = Message::new(&budgeted, Some(payer));
+    let num_signatures = usize::from(message.header.num_required_signatures);
+    // a signature count below 128 takes a single byte
+    1 + num_signatures * 64 + message.serialize().len()
+}
+
+/// Merges consecutive groups of instructions into as few transactions as fit in a
+/// packet. A group is never split; one larger than a packet gets a transaction of its own.
+pub fn pack_instructions(groups: Vec<Vec<Instruction>>, payer: &Pubkey) -> Vec<Vec<Instruction>> {
+    let mut packed: Vec<Vec<Instruction>> = Vec::new();
+    for group in groups.into_iter().filter(|group| !group.is_empty()) {
+        if let Some(last) = packed.last_mut() {
+            let mut merged = last.clone();
+            merged.extend(group.iter().cloned());
+            if txn_size(&merged, payer) <= PACKET_DATA_SIZE {
+                *last = merged;
+                continue;
+            }
+        }
+        packed.push(group);
+    }
+    packed
+}
+
+/// Sends the groups of instructions in order, packed with [`pack_instructions`], each
+/// transaction signed by the `signers` its instructions require.
+///
+/// With `dry_run` set, every transaction is simulated against the current state, so a
+/// transaction depending on an earlier one may fail; those failures are printed only.
+pub fn send_txns(
+    config: &Config,
+    client: &RpcClient,
+    groups: Vec<Vec<Instruction>>,
+    payer: &Keypair,
+    signers: &[&Keypair],
+) -> Result<Vec<TxnResult>> {
+    let batches = pack_instructions(groups, &payer.pubkey());
+    let mut results = Vec::new();
+    for (index, instructions) in batches.iter().enumerate() {
+        let txn_signers: Vec<&Keypair> = signers
+            .iter()
+            .copied()
+            .filter(|signer| {
+                signer.pubkey() == payer.pubkey()
+                    || instructions.iter().any(|instruction| {
+                        instruction
+                            .accounts
+                            .iter()
+                            .any(|meta| meta.is_signer && meta.pubkey == signer.pubkey())
+                    })
+            })
+            .collect();
+        match send_txn(config, client, instructions, payer, &txn_signers) {
+            Ok(result) => {
+                println!("Transaction {}/{}: {}", index + 1, batches.len(), result);
+                results.push(result);
+            }
+            Err(ClientError::Simulation(error)) if config.global.dry_run && index > 0 => {
+                println!(
+                    "Transaction {}/{} depends on the earlier ones: {}",
+                    index + 1,
+                    batches.len(),
+                    error
+                );
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(results)
+}
+
+/// Returns the status of a signature, treating retriable RPC errors as an unknown status.
+fn get_signature_status(
+    client: &RpcClient,
+    signature: &Signature,
+) -> Result<Option<TransactionStatus>> {
+    match client.get_signature_statuses(&[*signature]) {
+        Ok(statuses) => Ok(statuses.value.into_iter().next().flatten()),
+        Err(err) if is_retriable_error(&err) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Fee, compute units and logs of a landed transaction.
+struct TxnDetails {
+    fee: u64,
+    compute_units_consumed: Option<u64>,
+    logs: Vec<String>,
+}
+
+/// Fetches the details of a transaction, waiting for it to be available at
+/// `confirmed` commitment.
+fn get_txn_details(client: &RpcClient, signature: &Signature) -> Option<TxnDetails> {
+    for _ in 0..MAX_FETCH_ATTEMPTS {
+        match client.get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        ) {
+            Ok(txn) => {
+                let meta = txn.transaction.meta?;
+                let logs: Option<Vec<String>> = meta.log_messages.into();
+                return Some(TxnDetails {
+                    fee: meta.fee,
+                    compute_units_consumed: meta.compute_units_consumed.into(),
+                    logs: logs.unwrap_or_default(),
+                });
+            }
+            Err(_) => sleep(POLL_INTERVAL),
+        }
+    }
+    None
+}
+
+/// Balance of a writable account before and after a simulated transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BalanceChange {
+    pub account: Pubkey,
+    pub lamports_before: u64,
+    pub lamports_after: u64,
+    /// Token amounts, for SPL Token and Token-2022 token accounts.
+    pub token_amount_before: Option<u64>,
+    pub token_amount_after: Option<u64>,
+}
+
+/// Outcome of `simulateTransaction`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimulationReport {
+    /// Slot the simulation ran at.
+    pub slot: u64,
+    pub units_consumed: Option<u64>,
+    pub logs: Vec<String>,
+    /// Writable accounts whose lamports or token amount changed.
+    pub balance_changes: Vec<BalanceChange>,
+    /// The transaction error, decoded against the program's error codes when possible.
+    pub error: Option<String>,
+}
+
+fn token_amount(account: &Account) -> Option<u64> {
+    if account.owner != spl_token::id() && account.owner != spl_token_2022::id() {
+        return None;
+    }
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+        .ok()
+        .map(|state| state.base.amount)
+}
+
+/// Simulates the transaction against the latest state, without signature verification.
+pub fn simulate_txn(client: &RpcClient, txn: &Transaction) -> Result<SimulationReport> {
+    let writable_accounts: Vec<Pubkey> = txn
+        .message
+        .account_keys
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| txn.message.is_writable(*index))
+        .map(|(_, key)| *key)
+        .collect();
+    let accounts_before = client.get_multiple_accounts(&writable_accounts)?;
+
+    let response = client.simulate_transaction_with_config(
         txn,
-        if wait_confirm {
-            CommitmentConfig::confirmed()
-        } else {
-            CommitmentConfig::processed()
-        },
-        RpcSendTransactionConfig {
-            skip_preflight: true,
-            ..RpcSendTransactionConfig::default()
+        RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(client.commitment()),
+            accounts: Some(RpcSimulateTransactionAccountsConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                addresses: writable_accounts
+                    .iter()
+                    .map(|key| key.to_string())
+                    .collect(),
+            }),
+            ..RpcSimulateTransactionConfig::default()
         },
-    )?)
+    )?;
+    let slot = response.context.slot;
+    let result = response.value;
+
+    let logs = result.logs.unwrap_or_default();
+    let accounts_after = result.accounts.unwrap_or_default();
+    let mut balance_changes = Vec::new();
+    for (index, account) in writable_accounts.iter().enumerate() {
+        let before = accounts_before.get(index).cloned().flatten();
+        let after = accounts_after
+            .get(index)
+            .cloned()
+            .flatten()
+            .and_then(|account| account.decode::<Account>());
+        let change = BalanceChange {
+            account: *account,
+            lamports_before: before.as_ref().map_or(0, |account| account.lamports),
+            lamports_after: after.as_ref().map_or(0, |account| account.lamports),
+            token_amount_before: before.as_ref().and_then(token_amount),
+            token_amount_after: after.as_ref().and_then(token_amount),
+        };
+        if change.lamports_before != change.lamports_after
+            || change.token_amount_before != change.token_amount_after
+        {
+            balance_changes.push(change);
+        }
+    }
+
+    Ok(SimulationReport {
+        slot,
+        units_consumed: result.units_consumed,
+        error: result.err.map(|err| decode_transaction_error(&err, &logs)),
+        logs,
+        balance_changes,
+    })
+}
+
+/// Error codes of the CLMM program, looked up by number when the logs do not name the error.
+const RAYDIUM_ERROR_CODES: [RaydiumErrorCode; 44] = [
+    RaydiumErrorCode::LOK,
+    RaydiumErrorCode::NotApproved,
+    RaydiumErrorCode::InvalidUpdateConfigFlag,
+    RaydiumErrorCode::AccountLack,
+    RaydiumErrorCode::ClosePositionErr,
+    RaydiumErrorCode::ZeroMintAmount,
+    RaydiumErrorCode::InvaildTickIndex,
+    RaydiumErrorCode::TickInvaildOrder,
+    RaydiumErrorCode::TickLowerOverflow,
+    RaydiumErrorCode::TickUpperOverflow,
+    RaydiumErrorCode::TickAndSpacingNotMatch,
+    RaydiumErrorCode::InvalidTickArray,
+    RaydiumErrorCode::InvalidTickArrayBoundary,
+    RaydiumErrorCode::SqrtPriceLimitOverflow,
+    RaydiumErrorCode::SqrtPriceX64,
+    RaydiumErrorCode::LiquiditySubValueErr,
+    RaydiumErrorCode::LiquidityAddValueErr,
+    RaydiumErrorCode::InvaildLiquidity,
+    RaydiumErrorCode::ForbidBothZeroForSupplyLiquidity,
+    RaydiumErrorCode::LiquidityInsufficient,
+    RaydiumErrorCode::TransactionTooOld,
+    RaydiumErrorCode::PriceSlippageCheck,
+    RaydiumErrorCode::TooLittleOutputReceived,
+    RaydiumErrorCode::TooMuchInputPaid,
+    RaydiumErrorCode::ZeroAmountSpecified,
+    RaydiumErrorCode::InvalidInputPoolVault,
+    RaydiumErrorCode::TooSmallInputOrOutputAmount,
+    RaydiumErrorCode::NotEnoughTickArrayAccount,
+    RaydiumErrorCode::InvalidFirstTickArrayAccount,
+    RaydiumErrorCode::InvalidRewardIndex,
+    RaydiumErrorCode::FullRewardInfo,
+    RaydiumErrorCode::RewardTokenAlreadyInUse,
+    RaydiumErrorCode::ExceptPoolVaultMint,
+    RaydiumErrorCode::InvalidRewardInitParam,
+    RaydiumErrorCode::InvalidRewardDesiredAmount,
+    RaydiumErrorCode::InvalidRewardInputAccountNumber,
+    RaydiumErrorCode::InvalidRewardPeriod,
+    RaydiumErrorCode::NotApproveUpdateRewardEmissiones,
+    RaydiumErrorCode::UnInitializedRewardInfo,
+    RaydiumErrorCode::NotSupportMint,
+    RaydiumErrorCode::MissingTickArrayBitmapExtensionAccount,
+    RaydiumErrorCode::InsufficientLiquidityForDirection,
+    RaydiumErrorCode::MaxTokenOverflow,
+    RaydiumErrorCode::CalculateOverflow,
+];
+
+/// Describes a transaction error, using the anchor error log of the failing program
+/// (error name, number and message) when there is one. When the logs are missing or
+/// truncated, a custom program error is looked up in the CLMM program's error codes.
+pub fn decode_transaction_error(err: &TransactionError, logs: &[String]) -> String {
+    let anchor_error = logs
+        .iter()
+        .rev()
+        .find_map(|log| {
+            log.find("Error Code: ")
+                .map(|position| log[position..].trim_end().to_string())
+        })
+        .or_else(|| match err {
+            TransactionError::InstructionError(_, InstructionError::Custom(number)) => {
+                RAYDIUM_ERROR_CODES
+                    .iter()
+                    .find(|code| u32::from(**code) == *number)
+                    .map(|code| {
+                        format!(
+                            "Error Code: {}. Error Number: {}. Error Message: {}.",
+                            code.name(),
+                            number,
+                            code
+                        )
+                    })
+            }
+            _ => None,
+        });
+    match anchor_error {
+        Some(anchor_error) => format!("{} ({})", anchor_error, err),
+        None => err.to_string(),
+    }
+}
+
+/// Prints the compute units, logs, balance changes and error of a simulation.
+pub fn print_simulation_report(report: &SimulationReport) {
+    println!("Simulation (dry run, nothing was sent):");
+    match report.units_consumed {
+        Some(units_consumed) => println!("  Compute units consumed: {}", units_consumed),
+        None => println!("  Compute units consumed: unknown"),
+    }
+    println!("  Logs:");
+    for log in &report.logs {
+        println!("    {}", log);
+    }
+    println!("  Balance changes:");
+    for change in &report.balance_changes {
+        let lamports_delta = change.lamports_after as i128 - change.lamports_before as i128;
+        match (change.token_amount_before, change.token_amount_after) {
+            (None, None) => println!("    {}: {:+} lamports", change.account, lamports_delta),
+            (before, after) => println!(
+                "    {}: {:+} lamports, {:+} tokens",
+                change.account,
+                lamports_delta,
+                after.unwrap_or(0) as i128 - before.unwrap_or(0) as i128
+            ),
+        }
+    }
+    if let Some(error) = &report.error {
+        println!("  Error: {}", error);
+    }
 }
@@ -0,0 +1,340 @@
+use anchor_client::{Client, Cluster};
+use anchor_lang::prelude::AccountMeta;
+use raydium_amm_v3::accounts as raydium_accounts;
+use raydium_amm_v3::instruction as raydium_instruction;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+use crate::quote::fetch_quote_accounts;
+use crate::quote::sqrt_price_limit_x64;
+use crate::quote::{QuoteAccounts, SwapQuote};
+use crate::utils::amount_with_slippage;
+use crate::utils::get_tick_array_bitmap;
+use crate::utils::get_tick_array_key;
+use crate::utils::PoolMints;
+use crate::wsol::{is_native_mint, user_token_account, WsolAccount};
+use crate::{
+    config::Config,
+    error::{ClientError, Result},
+    rpc::send_txn,
+    utils::read_keypair_file,
+};
+
+/// Swaps `amount` of `input_mint`, or for `amount` of the other mint when
+/// `is_base_input` is unset, stopping at `limit_price`, quoted in `quote_mint`, token_1
+/// by default.
+#[allow(clippy::too_many_arguments)]
+pub fn swap(
+    config: &Config,
+    pool_pubkey: Pubkey,
+    input_mint: Pubkey,
+    amount: u64,
+    is_base_input: bool,
+    limit_price: Option<&str>,
+    quote_mint: Option<&Pubkey>,
+    slippage: f64,
+) -> Result<()> {
+    let payer = read_keypair_file(&config.global.payer_path)?;
+
+    let url = Cluster::Custom(config.global.http_url.clone(), config.global.ws_url.clone());
+    let client = Client::new(url, &payer);
+    let rpc_client = config.rpc_client()?;
+
+    let program = client.program(config.raydium_v3_program_id()?)?;
+    let program_pubkey = program.id();
+
+    let accounts = fetch_quote_accounts(&rpc_client, &program_pubkey, pool_pubkey)?;
+    let quote_accounts = QuoteAccounts::from_accounts(&accounts)?;
+    let pool = &quote_accounts.pool_state;
+
+    let zero_for_one = if input_mint == pool.token_mint_0 {
+        true
+    } else if input_mint == pool.token_mint_1 {
+        false
+    } else {
+        return Err(ClientError::InvalidArgument(format!(
+            "mint {} does not belong to pool {}",
+            input_mint, pool_pubkey
+        )));
+    };
+
+    let sqrt_price_limit_x64 = limit_price
+        .map(|price| sqrt_price_limit_x64(pool, price, quote_mint))
+        .transpose()?;
+
+    let mints = PoolMints::fetch(&rpc_client, pool.token_mint_0, pool.token_mint_1)?;
+    let (input_vault_mint, output_vault_mint) = if zero_for_one {
+        (pool.token_mint_0, pool.token_mint_1)
+    } else {
+        (pool.token_mint_1, pool.token_mint_0)
+    };
+    // native SOL is paid from, or received in, a temporary account unwrapped at the end,
+    // funded once the amount paid is known
+    let mut wsol = WsolAccount::for_mint(config, &input_vault_mint, 0)
+        .or_else(|| WsolAccount::for_mint(config, &output_vault_mint, 0));
+    let user_token_accounts = (
+        user_token_account(
+            wsol.as_ref(),
+            &payer.pubkey(),
+            &mints.mint_0,
+            &mints.account_0.owner,
+        ),
+        user_token_account(
+            wsol.as_ref(),
+            &payer.pubkey(),
+            &mints.mint_1,
+            &mints.account_1.owner,
+        ),
+    );
+
+    let swap = swap_instrs(
+        config,
+        &payer,
+        &quote_accounts,
+        &mints,
+        user_token_accounts,
+        zero_for_one,
+        amount,
+        is_base_input,
+        sqrt_price_limit_x64,
+        slippage,
+    )?;
+    let (input_token, output_token) = if zero_for_one {
+        ("token_0", "token_1")
+    } else {
+        ("token_1", "token_0")
+    };
+    if is_base_input {
+        println!(
+            "Swapping {} {} for at least {} {}",
+            amount, input_token, swap.other_amount_threshold, output_token
+        );
+    } else {
+        println!(
+            "Swapping at most {} {} for {} {}",
+            swap.other_amount_threshold, input_token, amount, output_token
+        );
+    }
+
+    let mut instructions = Vec::new();
+    if let Some(wsol) = &mut wsol {
+        if is_native_mint(&input_vault_mint) {
+            wsol.lamports = if is_base_input {
+                amount
+            } else {
+                swap.other_amount_threshold
+            };
+        }
+        instructions.extend(wsol.wrap_instrs(&rpc_client, &payer.pubkey(), &payer.pubkey())?);
+    }
+    instructions.extend(swap.instructions);
+    if let Some(wsol) = &wsol {
+        instructions.push(wsol.unwrap_instr(&payer.pubkey())?);
+    }
+    // send
+    let mut signers = vec![&payer];
+    signers.extend(wsol.as_ref().map(|wsol| &wsol.keypair));
+    let result = send_txn(config, &rpc_client, &instructions, &payer, &signers)?;
+    println!("Swapped: {}", result);
+
+    Ok(())
+}
+
+/// Swap instructions together with the quote they were sized from.
+pub struct SwapInstrs {
+    pub instructions: Vec<Instruction>,
+    pub quote: SwapQuote,
+    /// Least amount received for an exact input, or most amount paid for an exact
+    /// output, after slippage and transfer fees.
+    pub other_amount_threshold: u64,
+}
+
+/// Builds the instructions swapping `amount` of the input token, or for `amount` of the
+/// output token when `is_base_input` is unset, against the pool of `quote_accounts`.
+///
+/// The amount is adjusted for the transfer fees of `mints` and quoted locally, and the
+/// other amount is bounded by `slippage`. `user_token_accounts` are the payer's token_0
+/// and token_1 accounts.
+#[allow(clippy::too_many_arguments)]
+pub fn swap_instrs(
+    config: &Config,
+    payer: &Keypair,
+    quote_accounts: &QuoteAccounts,
+    mints: &PoolMints,
+    user_token_accounts: (Pubkey, Pubkey),
+    zero_for_one: bool,
+    amount: u64,
+    is_base_input: bool,
+    sqrt_price_limit_x64: Option<u128>,
+    slippage: f64,
+) -> Result<SwapInstrs> {
+    let pool = &quote_accounts.pool_state;
+    let pool_pubkey = quote_accounts.pool_pubkey;
+    let program_pubkey = config.raydium_v3_program_id()?;
+
+    // the pool only receives the input amount net of the input mint's transfer fee
+    let (input_transfer_fee, output_transfer_fee) = if zero_for_one {
+        let transfer_fee = mints.transfer_fee(amount, 0)?;
+        (transfer_fee.0, transfer_fee.1)
+    } else {
+        let transfer_fee = mints.transfer_fee(0, amount)?;
+        (transfer_fee.1, transfer_fee.0)
+    };
+    // and must send the output amount plus the output mint's transfer fee, which the
+    // program adds itself
+    let amount_specified = if is_base_input {
+        amount
+            .checked_sub(input_transfer_fee.transfer_fee)
+            .ok_or_else(|| {
+                ClientError::InvalidArgument("amount does not cover the transfer fee".to_string())
+            })?
+    } else {
+        let output_inverse_fee = if zero_for_one {
+            mints.inverse_fee(0, amount)?.1
+        } else {
+            mints.inverse_fee(amount, 0)?.0
+        };
+        amount
+            .checked_add(output_inverse_fee.transfer_fee)
+            .ok_or_else(|| ClientError::MathOverflow("amount_specified".to_string()))?
+    };
+
+    let quote = quote_accounts.quote(
+        zero_for_one,
+        is_base_input,
+        amount_specified,
+        sqrt_price_limit_x64,
+    )?;
+    let other_amount = if is_base_input {
+        quote.amount_out
+    } else {
+        quote.amount_in
+    };
+
+    let other_amount_threshold = if is_base_input {
+        // calc min out with slippage and the output mint's transfer fee
+        let amount_out_with_slippage = amount_with_slippage(other_amount, slippage, false)?;
+        let transfer_fee = if zero_for_one {
+            mints.transfer_fee(0, amount_out_with_slippage)?.1
+        } else {
+            mints.transfer_fee(amount_out_with_slippage, 0)?.0
+        };
+        amount_out_with_slippage.saturating_sub(transfer_fee.transfer_fee)
+    } else {
+        // calc max in with slippage and the input mint's transfer fee
+        let amount_in_with_slippage = amount_with_slippage(other_amount, slippage, true)?;
+        let transfer_fee = if zero_for_one {
+            mints.inverse_fee(amount_in_with_slippage, 0)?.0
+        } else {
+            mints.inverse_fee(0, amount_in_with_slippage)?.1
+        };
+        amount_in_with_slippage
+            .checked_add(transfer_fee.transfer_fee)
+            .ok_or_else(|| ClientError::MathOverflow("other_amount_threshold".to_string()))?
+    };
+
+    let mut remaining_accounts = Vec::new();
+    remaining_accounts.push(AccountMeta::new_readonly(
+        get_tick_array_bitmap(
+            &pool.amm_config,
+            &pool.token_mint_0,
+            &pool.token_mint_1,
+            &program_pubkey,
+        ),
+        false,
+    ));
+    remaining_accounts.extend(quote.tick_array_start_indexes.iter().map(|index| {
+        AccountMeta::new(
+            get_tick_array_key(&pool_pubkey, *index, &program_pubkey),
+            false,
+        )
+    }));
+
+    let (input_vault, output_vault, user_input_token, user_output_token) = if zero_for_one {
+        (
+            pool.token_vault_0,
+            pool.token_vault_1,
+            user_token_accounts.0,
+            user_token_accounts.1,
+        )
+    } else {
+        (
+            pool.token_vault_1,
+            pool.token_vault_0,
+            user_token_accounts.1,
+            user_token_accounts.0,
+        )
+    };
+    let instructions = swap_v2_instr(
+        config,
+        payer,
+        pool.amm_config,
+        pool_pubkey,
+        input_vault,
+        output_vault,
+        pool.observation_key,
+        user_input_token,
+        user_output_token,
+        input_transfer_fee.mint,
+        output_transfer_fee.mint,
+        remaining_accounts,
+        amount,
+        other_amount_threshold,
+        sqrt_price_limit_x64,
+        is_base_input,
+    )?;
+    Ok(SwapInstrs {
+        instructions,
+        quote,
+        other_amount_threshold,
+    })
+}
+
+pub fn swap_v2_instr(
+    config: &Config,
+    payer: &Keypair,
+    amm_config: Pubkey,
+    pool_account_key: Pubkey,
+    input_vault: Pubkey,
+    output_vault: Pubkey,
+    observation_state: Pubkey,
+    user_input_token: Pubkey,
+    user_output_token: Pubkey,
+    input_vault_mint: Pubkey,
+    output_vault_mint: Pubkey,
+    remaining_accounts: Vec<AccountMeta>,
+    amount: u64,
+    other_amount_threshold: u64,
+    sqrt_price_limit_x64: Option<u128>,
+    is_base_input: bool,
+) -> Result<Vec<Instruction>> {
+    let url = Cluster::Custom(config.global.http_url.clone(), config.global.ws_url.clone());
+    let client = Client::new(url, payer);
+
+    let program = client.program(config.raydium_v3_program_id()?)?;
+    let instructions = program
+        .request()
+        .accounts(raydium_accounts::SwapSingleV2 {
+            payer: program.payer(),
+            amm_config,
+            pool_state: pool_account_key,
+            input_token_account: user_input_token,
+            output_token_account: user_output_token,
+            input_vault,
+            output_vault,
+            observation_state,
+            token_program: spl_token::id(),
+            token_program_2022: spl_token_2022::id(),
+            memo_program: spl_memo::id(),
+            input_vault_mint,
+            output_vault_mint,
+        })
+        .accounts(remaining_accounts)
+        .args(raydium_instruction::SwapV2 {
+            amount,
+            other_amount_threshold,
+            sqrt_price_limit_x64: sqrt_price_limit_x64.unwrap_or(0u128),
+            is_base_input,
+        })
+        .instructions()?;
+    Ok(instructions)
+}
@@ -1,9 +1,13 @@
 use std::{env, ops::Mul};
 
 use anchor_lang::AccountDeserialize;
-use anyhow::{anyhow, Result};
 
-use raydium_amm_v3::states::POOL_TICK_ARRAY_BITMAP_SEED;
+use raydium_amm_v3::libraries::tick_math;
+use raydium_amm_v3::states::{
+    PersonalPositionState, PoolState, AMM_CONFIG_SEED, POOL_SEED, POOL_TICK_ARRAY_BITMAP_SEED,
+    POOL_VAULT_SEED, POSITION_SEED, TICK_ARRAY_SEED,
+};
+use serde::Serializer;
 use solana_account_decoder::{
     parse_token::{TokenAccountType, UiAccountState},
     UiAccountData,
@@ -18,30 +22,50 @@ use spl_token_2022::{
     state::Mint,
 };
 
+use crate::error::{ClientError, Result};
+use crate::pool::{LiquidityBucket, PoolSummary};
+use crate::positions::PositionSummary;
+use crate::price;
+
 const Q64: u128 = (u64::MAX as u128) + 1; // 2^64
 
+/// Maximum number of accounts accepted by a single `getMultipleAccounts` request.
+pub(crate) const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+/// Serializes a public key as its base58 string.
+pub(crate) fn serialize_pubkey<S: Serializer>(
+    pubkey: &Pubkey,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_str(pubkey)
+}
+
 pub fn multipler(decimals: u8) -> f64 {
-    (10_i32).checked_pow(decimals.try_into().unwrap()).unwrap() as f64
+    10_f64.powi(decimals.into())
 }
 
 pub fn price_to_x64(price: f64) -> u128 {
     (price * Q64 as f64) as u128
 }
 
-pub fn price_to_sqrt_price_x64(price: f64, decimals_0: u8, decimals_1: u8) -> u128 {
-    let price_with_decimals = price * multipler(decimals_1) / multipler(decimals_0);
-    price_to_x64(price_with_decimals.sqrt())
+pub fn x64_to_price(price_x64: u128) -> f64 {
+    price_x64 as f64 / Q64 as f64
 }
 
 pub fn read_keypair_file(s: &str) -> Result<Keypair> {
     let expanded = if s.starts_with("~") {
-        let home = env::var("HOME").map_err(|_| anyhow!("HOME environment variable is not set"))?;
+        let home = env::var("HOME").map_err(|_| ClientError::Keypair {
+            path: s.to_string(),
+            reason: "HOME environment variable is not set".to_string(),
+        })?;
         s.replacen("~", &home, 1)
     } else {
         s.to_string()
     };
-    solana_sdk::signature::read_keypair_file(expanded)
-        .map_err(|e| anyhow!("failed to read keypair from {}", e))
+    solana_sdk::signature::read_keypair_file(&expanded).map_err(|e| ClientError::Keypair {
+        path: expanded,
+        reason: e.to_string(),
+    })
 }
 
 pub fn get_tick_array_bitmap(
@@ -73,9 +97,262 @@ pub fn get_tick_array_bitmap(
     tickarray_bitmap_extension
 }
 
+/// Orders two mints the way the program requires for a pool: `token_mint_0` is the
+/// smaller one.
+pub fn sort_mints(mint_a: Pubkey, mint_b: Pubkey) -> (Pubkey, Pubkey) {
+    if mint_a > mint_b {
+        (mint_b, mint_a)
+    } else {
+        (mint_a, mint_b)
+    }
+}
+
+/// Returns whether prices quoted in `quote_mint` are the inverse of the prices of the
+/// pair, which are in `mint_1` per `mint_0`. Without a quote mint, prices are in `mint_1`.
+pub fn price_inverted(
+    mint_0: &Pubkey,
+    mint_1: &Pubkey,
+    quote_mint: Option<&Pubkey>,
+) -> Result<bool> {
+    match quote_mint {
+        None => Ok(false),
+        Some(quote_mint) if quote_mint == mint_1 => Ok(false),
+        Some(quote_mint) if quote_mint == mint_0 => Ok(true),
+        Some(quote_mint) => Err(ClientError::InvalidArgument(format!(
+            "quote mint {} is neither {} nor {}",
+            quote_mint, mint_0, mint_1
+        ))),
+    }
+}
+
+/// Converts a decimal price to the sqrt price of a pair, reading it as token_0 per
+/// token_1 when the prices are inverted.
+pub fn quoted_price_to_sqrt_price_x64(
+    price: &str,
+    decimals_0: u8,
+    decimals_1: u8,
+    inverted: bool,
+) -> Result<u128> {
+    if inverted {
+        price::inverse_price_to_sqrt_price_x64(price, decimals_0, decimals_1)
+    } else {
+        price::price_to_sqrt_price_x64(price, decimals_0, decimals_1)
+    }
+}
+
+/// Prints the sqrt price of a pair as a decimal price, in token_0 per token_1 when the
+/// prices are inverted. The decimal converts back exactly with
+/// [`quoted_price_to_sqrt_price_x64`].
+pub fn sqrt_price_x64_to_quoted_price(
+    sqrt_price_x64: u128,
+    decimals_0: u8,
+    decimals_1: u8,
+    inverted: bool,
+) -> Result<String> {
+    if inverted {
+        price::sqrt_price_x64_to_inverse_price(sqrt_price_x64, decimals_0, decimals_1)
+    } else {
+        Ok(price::sqrt_price_x64_to_price(
+            sqrt_price_x64,
+            decimals_0,
+            decimals_1,
+        ))
+    }
+}
+
+/// Prints the prices at the bounds of a tick range, swapped when the prices are
+/// inverted so that the lower one comes first.
+pub fn tick_range_to_quoted_prices(
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    decimals_0: u8,
+    decimals_1: u8,
+    inverted: bool,
+) -> Result<(String, String)> {
+    let price_at_tick = |tick: i32| -> Result<String> {
+        sqrt_price_x64_to_quoted_price(
+            tick_math::get_sqrt_price_at_tick(tick)?,
+            decimals_0,
+            decimals_1,
+            inverted,
+        )
+    };
+    if inverted {
+        Ok((
+            price_at_tick(tick_upper_index)?,
+            price_at_tick(tick_lower_index)?,
+        ))
+    } else {
+        Ok((
+            price_at_tick(tick_lower_index)?,
+            price_at_tick(tick_upper_index)?,
+        ))
+    }
+}
+
+/// Values both vaults of a pool at `sqrt_price_x64` in whole tokens of token_1, or of
+/// token_0 when the prices are inverted.
+pub fn vaults_value(
+    vault_amount_0: u64,
+    vault_amount_1: u64,
+    sqrt_price_x64: u128,
+    decimals_0: u8,
+    decimals_1: u8,
+    inverted: bool,
+) -> f64 {
+    // the raw price converts base units of token_0 to base units of token_1
+    let raw_price = x64_to_price(sqrt_price_x64).powi(2);
+    if inverted {
+        (vault_amount_0 as f64 + vault_amount_1 as f64 / raw_price) / multipler(decimals_0)
+    } else {
+        (vault_amount_0 as f64 * raw_price + vault_amount_1 as f64) / multipler(decimals_1)
+    }
+}
+
+/// Returns the pool with its price and TVL in `quote_mint`, token_1 by default.
+pub fn quote_pool(pool: &PoolSummary, quote_mint: Option<&Pubkey>) -> Result<PoolSummary> {
+    let inverted = price_inverted(&pool.mint_0, &pool.mint_1, quote_mint)?;
+    Ok(PoolSummary {
+        quote_mint: if inverted { pool.mint_0 } else { pool.mint_1 },
+        price: sqrt_price_x64_to_quoted_price(
+            pool.sqrt_price_x64,
+            pool.decimals_0,
+            pool.decimals_1,
+            inverted,
+        )?,
+        tvl: vaults_value(
+            pool.vault_amount_0,
+            pool.vault_amount_1,
+            pool.sqrt_price_x64,
+            pool.decimals_0,
+            pool.decimals_1,
+            inverted,
+        ),
+        ..pool.clone()
+    })
+}
+
+/// Returns the position with its prices in `quote_mint`, token_1 of its pool by default.
+/// The bounds of an inverted range are swapped, so that the lower price comes first.
+pub fn quote_position(
+    position: &PositionSummary,
+    quote_mint: Option<&Pubkey>,
+) -> Result<PositionSummary> {
+    let inverted = price_inverted(&position.mint_0, &position.mint_1, quote_mint)?;
+    let (price_lower, price_upper) = tick_range_to_quoted_prices(
+        position.tick_lower_index,
+        position.tick_upper_index,
+        position.decimals_0,
+        position.decimals_1,
+        inverted,
+    )?;
+    Ok(PositionSummary {
+        price_lower,
+        price_upper,
+        price_current: sqrt_price_x64_to_quoted_price(
+            position.sqrt_price_x64,
+            position.decimals_0,
+            position.decimals_1,
+            inverted,
+        )?,
+        ..position.clone()
+    })
+}
+
+/// Returns the liquidity buckets of `pool` with their prices in `quote_mint`, token_1 by
+/// default.
+pub fn quote_liquidity_buckets(
+    buckets: &[LiquidityBucket],
+    pool: &PoolSummary,
+    quote_mint: Option<&Pubkey>,
+) -> Result<Vec<LiquidityBucket>> {
+    let inverted = price_inverted(&pool.mint_0, &pool.mint_1, quote_mint)?;
+    buckets
+        .iter()
+        .map(|bucket| {
+            let (price_lower, price_upper) = tick_range_to_quoted_prices(
+                bucket.tick_lower,
+                bucket.tick_upper,
+                pool.decimals_0,
+                pool.decimals_1,
+                inverted,
+            )?;
+            Ok(LiquidityBucket {
+                price_lower,
+                price_upper,
+                ..bucket.clone()
+            })
+        })
+        .collect()
+}
+
+pub fn get_amm_config_key(config_index: u16, raydium_v3_program: &Pubkey) -> Pubkey {
+    let (amm_config_key, _bump) = Pubkey::find_program_address(
+        &[AMM_CONFIG_SEED.as_bytes(), &config_index.to_be_bytes()],
+        raydium_v3_program,
+    );
+    amm_config_key
+}
+
+pub fn get_pool_key(
+    amm_config_key: &Pubkey,
+    token_mint_0: &Pubkey,
+    token_mint_1: &Pubkey,
+    raydium_v3_program: &Pubkey,
+) -> Pubkey {
+    let (pool_key, _bump) = Pubkey::find_program_address(
+        &[
+            POOL_SEED.as_bytes(),
+            amm_config_key.to_bytes().as_ref(),
+            token_mint_0.to_bytes().as_ref(),
+            token_mint_1.to_bytes().as_ref(),
+        ],
+        raydium_v3_program,
+    );
+    pool_key
+}
+
+pub fn get_pool_vault_key(pool_key: &Pubkey, mint: &Pubkey, raydium_v3_program: &Pubkey) -> Pubkey {
+    let (vault_key, _bump) = Pubkey::find_program_address(
+        &[
+            POOL_VAULT_SEED.as_bytes(),
+            pool_key.to_bytes().as_ref(),
+            mint.to_bytes().as_ref(),
+        ],
+        raydium_v3_program,
+    );
+    vault_key
+}
+
+pub fn get_tick_array_key(
+    pool: &Pubkey,
+    tick_array_start_index: i32,
+    program_id: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            TICK_ARRAY_SEED.as_bytes(),
+            pool.to_bytes().as_ref(),
+            &tick_array_start_index.to_be_bytes(),
+        ],
+        program_id,
+    )
+    .0
+}
+
 pub fn deserialize_anchor_account<T: AccountDeserialize>(account: &Account) -> Result<T> {
     let mut data: &[u8] = &account.data;
-    T::try_deserialize(&mut data).map_err(Into::into)
+    T::try_deserialize(&mut data)
+        .map_err(|e| ClientError::Deserialization(format!("{}: {}", std::any::type_name::<T>(), e)))
+}
+
+/// Fetches and deserializes an anchor account, failing if it does not exist.
+pub fn get_anchor_account<T: AccountDeserialize>(client: &RpcClient, pubkey: &Pubkey) -> Result<T> {
+    let account = client
+        .get_account_with_commitment(pubkey, client.commitment())?
+        .value
+        .ok_or(ClientError::AccountNotFound(*pubkey))?;
+    deserialize_anchor_account(&account)
 }
 
 pub fn tick_with_spacing(tick: i32, tick_spacing: i32) -> i32 {
@@ -86,11 +363,17 @@ pub fn tick_with_spacing(tick: i32, tick_spacing: i32) -> i32 {
     compressed * tick_spacing
 }
 
-pub fn amount_with_slippage(amount: u64, slippage: f64, round_up: bool) -> u64 {
+pub fn amount_with_slippage(amount: u64, slippage: f64, round_up: bool) -> Result<u64> {
+    if !(0.0..1.0).contains(&slippage) {
+        return Err(ClientError::Slippage(format!(
+            "slippage {} is not within 0..1",
+            slippage
+        )));
+    }
     if round_up {
-        (amount as f64).mul(1_f64 + slippage).ceil() as u64
+        Ok((amount as f64).mul(1_f64 + slippage).ceil() as u64)
     } else {
-        (amount as f64).mul(1_f64 - slippage).floor() as u64
+        Ok((amount as f64).mul(1_f64 - slippage).floor() as u64)
     }
 }
 
@@ -101,32 +384,102 @@ pub struct TransferFeeInfo {
     pub transfer_fee: u64,
 }
 
+/// The two mints of a pool and the current epoch, fetched once so that transfer fees can
+/// be computed locally for any number of amounts.
+pub struct PoolMints {
+    pub mint_0: Pubkey,
+    pub mint_1: Pubkey,
+    pub account_0: Account,
+    pub account_1: Account,
+    pub epoch: u64,
+}
+
+impl PoolMints {
+    pub fn fetch(
+        rpc_client: &RpcClient,
+        token_mint_0: Pubkey,
+        token_mint_1: Pubkey,
+    ) -> Result<Self> {
+        let load_accounts = vec![token_mint_0, token_mint_1];
+        let rsps = rpc_client.get_multiple_accounts(&load_accounts)?;
+        let account_0 = rsps[0]
+            .clone()
+            .ok_or(ClientError::AccountNotFound(token_mint_0))?;
+        let account_1 = rsps[1]
+            .clone()
+            .ok_or(ClientError::AccountNotFound(token_mint_1))?;
+        let epoch = rpc_client.get_epoch_info()?.epoch;
+        Ok(Self {
+            mint_0: token_mint_0,
+            mint_1: token_mint_1,
+            account_0,
+            account_1,
+            epoch,
+        })
+    }
+
+    /// Transfer fees charged on sending `pre_fee_amount_0` and `pre_fee_amount_1`.
+    pub fn transfer_fee(
+        &self,
+        pre_fee_amount_0: u64,
+        pre_fee_amount_1: u64,
+    ) -> Result<(TransferFeeInfo, TransferFeeInfo)> {
+        let mint0_state = unpack_mint(&self.mint_0, &self.account_0)?;
+        let mint1_state = unpack_mint(&self.mint_1, &self.account_1)?;
+        Ok((
+            TransferFeeInfo {
+                mint: self.mint_0,
+                owner: self.account_0.owner,
+                transfer_fee: get_transfer_fee(&mint0_state, self.epoch, pre_fee_amount_0)?,
+            },
+            TransferFeeInfo {
+                mint: self.mint_1,
+                owner: self.account_1.owner,
+                transfer_fee: get_transfer_fee(&mint1_state, self.epoch, pre_fee_amount_1)?,
+            },
+        ))
+    }
+
+    /// Transfer fees to add so that `post_fee_amount_0` and `post_fee_amount_1` arrive.
+    pub fn inverse_fee(
+        &self,
+        post_fee_amount_0: u64,
+        post_fee_amount_1: u64,
+    ) -> Result<(TransferFeeInfo, TransferFeeInfo)> {
+        let mint0_state = unpack_mint(&self.mint_0, &self.account_0)?;
+        let mint1_state = unpack_mint(&self.mint_1, &self.account_1)?;
+        Ok((
+            TransferFeeInfo {
+                mint: self.mint_0,
+                owner: self.account_0.owner,
+                transfer_fee: get_transfer_inverse_fee(
+                    &mint0_state,
+                    self.epoch,
+                    post_fee_amount_0,
+                )?,
+            },
+            TransferFeeInfo {
+                mint: self.mint_1,
+                owner: self.account_1.owner,
+                transfer_fee: get_transfer_inverse_fee(
+                    &mint1_state,
+                    self.epoch,
+                    post_fee_amount_1,
+                )?,
+            },
+        ))
+    }
+}
+
 pub fn get_pool_mints_inverse_fee(
     rpc_client: &RpcClient,
     token_mint_0: Pubkey,
     token_mint_1: Pubkey,
     post_fee_amount_0: u64,
     post_fee_amount_1: u64,
-) -> (TransferFeeInfo, TransferFeeInfo) {
-    let load_accounts = vec![token_mint_0, token_mint_1];
-    let rsps = rpc_client.get_multiple_accounts(&load_accounts).unwrap();
-    let epoch = rpc_client.get_epoch_info().unwrap().epoch;
-    let mint0_account = rsps[0].clone().ok_or("load mint0 rps error!").unwrap();
-    let mint1_account = rsps[1].clone().ok_or("load mint0 rps error!").unwrap();
-    let mint0_state = StateWithExtensions::<Mint>::unpack(&mint0_account.data).unwrap();
-    let mint1_state = StateWithExtensions::<Mint>::unpack(&mint1_account.data).unwrap();
-    (
-        TransferFeeInfo {
-            mint: token_mint_0,
-            owner: mint0_account.owner,
-            transfer_fee: get_transfer_inverse_fee(&mint0_state, post_fee_amount_0, epoch),
-        },
-        TransferFeeInfo {
-            mint: token_mint_1,
-            owner: mint1_account.owner,
-            transfer_fee: get_transfer_inverse_fee(&mint1_state, post_fee_amount_1, epoch),
-        },
-    )
+) -> Result<(TransferFeeInfo, TransferFeeInfo)> {
+    PoolMints::fetch(rpc_client, token_mint_0, token_mint_1)?
+        .inverse_fee(post_fee_amount_0, post_fee_amount_1)
 }
 
 pub fn get_pool_mints_transfer_fee(
@@ -135,26 +488,77 @@ pub fn get_pool_mints_transfer_fee(
     token_mint_1: Pubkey,
     pre_fee_amount_0: u64,
     pre_fee_amount_1: u64,
-) -> (TransferFeeInfo, TransferFeeInfo) {
-    let load_accounts = vec![token_mint_0, token_mint_1];
-    let rsps = rpc_client.get_multiple_accounts(&load_accounts).unwrap();
-    let epoch = rpc_client.get_epoch_info().unwrap().epoch;
-    let mint0_account = rsps[0].clone().ok_or("load mint0 rps error!").unwrap();
-    let mint1_account = rsps[1].clone().ok_or("load mint0 rps error!").unwrap();
-    let mint0_state = StateWithExtensions::<Mint>::unpack(&mint0_account.data).unwrap();
-    let mint1_state = StateWithExtensions::<Mint>::unpack(&mint1_account.data).unwrap();
-    (
-        TransferFeeInfo {
-            mint: token_mint_0,
-            owner: mint0_account.owner,
-            transfer_fee: get_transfer_fee(&mint0_state, epoch, pre_fee_amount_0),
-        },
-        TransferFeeInfo {
-            mint: token_mint_1,
-            owner: mint1_account.owner,
-            transfer_fee: get_transfer_fee(&mint1_state, epoch, pre_fee_amount_1),
-        },
-    )
+) -> Result<(TransferFeeInfo, TransferFeeInfo)> {
+    PoolMints::fetch(rpc_client, token_mint_0, token_mint_1)?
+        .transfer_fee(pre_fee_amount_0, pre_fee_amount_1)
+}
+
+/// Fetches accounts in batches of `MAX_MULTIPLE_ACCOUNTS`.
+pub(crate) fn get_multiple_accounts_chunked(
+    rpc_client: &RpcClient,
+    pubkeys: &[Pubkey],
+) -> Result<Vec<Option<Account>>> {
+    let mut accounts = Vec::with_capacity(pubkeys.len());
+    for chunk in pubkeys.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        accounts.extend(rpc_client.get_multiple_accounts(chunk)?);
+    }
+    Ok(accounts)
+}
+
+/// Returns the token program owning each of `mints`, either SPL Token or Token-2022.
+pub fn get_mint_token_programs(rpc_client: &RpcClient, mints: &[Pubkey]) -> Result<Vec<Pubkey>> {
+    if mints.is_empty() {
+        return Ok(Vec::new());
+    }
+    let rsps = rpc_client.get_multiple_accounts(mints)?;
+    mints
+        .iter()
+        .zip(rsps)
+        .map(|(mint, rsp)| {
+            let account = rsp.ok_or(ClientError::AccountNotFound(*mint))?;
+            if account.owner != spl_token::id() && account.owner != spl_token_2022::id() {
+                return Err(ClientError::InvalidArgument(format!(
+                    "{} is not a token mint",
+                    mint
+                )));
+            }
+            Ok(account.owner)
+        })
+        .collect()
+}
+
+/// Returns the token program owning `mint`, either SPL Token or Token-2022.
+pub fn get_mint_token_program(rpc_client: &RpcClient, mint: &Pubkey) -> Result<Pubkey> {
+    Ok(get_mint_token_programs(rpc_client, &[*mint])?[0])
+}
+
+/// Returns the token balances of `accounts`, counting missing accounts as empty.
+pub fn get_token_balances(rpc_client: &RpcClient, accounts: &[Pubkey]) -> Result<Vec<u64>> {
+    let rsps = get_multiple_accounts_chunked(rpc_client, accounts)?;
+    let mut balances = Vec::with_capacity(accounts.len());
+    for (key, rsp) in accounts.iter().zip(rsps) {
+        let balance = match rsp {
+            None => 0,
+            Some(account) => {
+                StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+                    .map_err(|e| {
+                        ClientError::Deserialization(format!("token account {}: {}", key, e))
+                    })?
+                    .base
+                    .amount
+            }
+        };
+        balances.push(balance);
+    }
+    Ok(balances)
+}
+
+pub fn unpack_mint<'data>(
+    mint: &Pubkey,
+    account: &'data Account,
+) -> Result<StateWithExtensions<'data, Mint>> {
+    StateWithExtensions::<Mint>::unpack(&account.data)
+        .map_err(|e| ClientError::Deserialization(format!("mint {}: {}", mint, e)))
 }
 
 /// Calculate the fee for input amount
@@ -162,15 +566,17 @@ pub fn get_transfer_fee<'data, S: BaseState>(
     account_state: &StateWithExtensions<'data, S>,
     epoch: u64,
     pre_fee_amount: u64,
-) -> u64 {
+) -> Result<u64> {
     let fee = if let Ok(transfer_fee_config) = account_state.get_extension::<TransferFeeConfig>() {
         transfer_fee_config
             .calculate_epoch_fee(epoch, pre_fee_amount)
-            .unwrap()
+            .ok_or_else(|| {
+                ClientError::MathOverflow(format!("transfer fee of {}", pre_fee_amount))
+            })?
     } else {
         0
     };
-    fee
+    Ok(fee)
 }
 
 /// Calculate the fee for output amount
@@ -178,7 +584,7 @@ pub fn get_transfer_inverse_fee<'data, S: BaseState>(
     account_state: &StateWithExtensions<'data, S>,
     epoch: u64,
     post_fee_amount: u64,
-) -> u64 {
+) -> Result<u64> {
     let fee = if let Ok(transfer_fee_config) = account_state.get_extension::<TransferFeeConfig>() {
         let transfer_fee = transfer_fee_config.get_epoch_fee(epoch);
         if u16::from(transfer_fee.transfer_fee_basis_points) == MAX_FEE_BASIS_POINTS {
@@ -186,12 +592,17 @@ pub fn get_transfer_inverse_fee<'data, S: BaseState>(
         } else {
             transfer_fee_config
                 .calculate_inverse_epoch_fee(epoch, post_fee_amount)
-                .unwrap()
+                .ok_or_else(|| {
+                    ClientError::MathOverflow(format!(
+                        "inverse transfer fee of {}",
+                        post_fee_amount
+                    ))
+                })?
         }
     } else {
         0
     };
-    fee
+    Ok(fee)
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -208,21 +619,21 @@ pub fn get_all_nft_and_position_by_owner(
     client: &RpcClient,
     owner: &Pubkey,
     raydium_amm_v3_program: &Pubkey,
-) -> Vec<PositionNftTokenInfo> {
+) -> Result<Vec<PositionNftTokenInfo>> {
     let mut spl_nfts = get_nft_account_and_position_by_owner(
         client,
         owner,
         spl_token::id(),
         raydium_amm_v3_program,
-    );
+    )?;
     let spl_2022_nfts = get_nft_account_and_position_by_owner(
         client,
         owner,
         spl_token_2022::id(),
         raydium_amm_v3_program,
-    );
+    )?;
     spl_nfts.extend(spl_2022_nfts);
-    spl_nfts
+    Ok(spl_nfts)
 }
 
 pub fn get_nft_account_and_position_by_owner(
@@ -230,10 +641,9 @@ pub fn get_nft_account_and_position_by_owner(
     owner: &Pubkey,
     token_program: Pubkey,
     raydium_amm_v3_program: &Pubkey,
-) -> Vec<PositionNftTokenInfo> {
-    let all_tokens = client
-        .get_token_accounts_by_owner(owner, TokenAccountsFilter::ProgramId(token_program))
-        .unwrap();
+) -> Result<Vec<PositionNftTokenInfo>> {
+    let all_tokens =
+        client.get_token_accounts_by_owner(owner, TokenAccountsFilter::ProgramId(token_program))?;
     let mut position_nft_accounts = Vec::new();
     for keyed_account in all_tokens {
         if let UiAccountData::Json(parsed_account) = keyed_account.account.data {
@@ -243,24 +653,19 @@ pub fn get_nft_account_and_position_by_owner(
                 {
                     let _frozen = ui_token_account.state == UiAccountState::Frozen;
 
-                    let token = ui_token_account
-                        .mint
-                        .parse::<Pubkey>()
-                        .unwrap_or_else(|err| panic!("Invalid mint: {}", err));
-                    let token_account = keyed_account
-                        .pubkey
-                        .parse::<Pubkey>()
-                        .unwrap_or_else(|err| panic!("Invalid token account: {}", err));
+                    let token = ui_token_account.mint.parse::<Pubkey>().map_err(|err| {
+                        ClientError::Deserialization(format!("invalid mint: {}", err))
+                    })?;
+                    let token_account = keyed_account.pubkey.parse::<Pubkey>().map_err(|err| {
+                        ClientError::Deserialization(format!("invalid token account: {}", err))
+                    })?;
                     let token_amount = ui_token_account
                         .token_amount
                         .amount
                         .parse::<u64>()
-                        .unwrap_or_else(|err| panic!("Invalid token amount: {}", err));
-
-                    let _close_authority = ui_token_account.close_authority.map_or(*owner, |s| {
-                        s.parse::<Pubkey>()
-                            .unwrap_or_else(|err| panic!("Invalid close authority: {}", err))
-                    });
+                        .map_err(|err| {
+                            ClientError::Deserialization(format!("invalid token amount: {}", err))
+                        })?;
 
                     if ui_token_account.token_amount.decimals == 0 && token_amount == 1 {
                         let (position_pda, _) = Pubkey::find_program_address(
@@ -283,5 +688,253 @@ pub fn get_nft_account_and_position_by_owner(
             }
         }
     }
-    position_nft_accounts
+    Ok(position_nft_accounts)
+}
+
+/// How a command addresses a position.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PositionSelector {
+    /// The position whose NFT has this mint.
+    NftMint(Pubkey),
+    /// The payer's position in the pool whose range the prices snap to. The prices are
+    /// in `quote_mint`, token_1 by default.
+    PriceRange {
+        tick_lower_price: String,
+        tick_upper_price: String,
+        quote_mint: Option<Pubkey>,
+    },
+    /// The payer's position in the pool with this tick range.
+    Range(TickRange),
+}
+
+impl PositionSelector {
+    /// Returns the tick indexes of the range addressed, or `None` for a position NFT.
+    pub fn tick_indexes(&self, pool: &PoolState) -> Result<Option<(i32, i32)>> {
+        match self {
+            PositionSelector::NftMint(_) => Ok(None),
+            PositionSelector::PriceRange {
+                tick_lower_price,
+                tick_upper_price,
+                quote_mint,
+            } => {
+                let inverted =
+                    price_inverted(&pool.token_mint_0, &pool.token_mint_1, quote_mint.as_ref())?;
+                price_range_to_tick_indexes(pool, tick_lower_price, tick_upper_price, inverted)
+                    .map(Some)
+            }
+            PositionSelector::Range(range) => range.tick_indexes(pool).map(Some),
+        }
+    }
+}
+
+/// A tick range given by tick indexes or relative to the current price of a pool.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TickRange {
+    /// Tick indexes, snapped down to the tick spacing.
+    Ticks {
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+    },
+    /// The current price minus and plus this percentage, snapped down to the tick spacing.
+    PercentAroundPrice(f64),
+    /// This many tick spacings below and above the tick spacing holding the current tick.
+    TickSpacings(u32),
+    /// The widest range the tick spacing allows.
+    FullRange,
+}
+
+impl TickRange {
+    /// Resolves the range against `pool`, within the full range of its tick spacing.
+    pub fn tick_indexes(&self, pool: &PoolState) -> Result<(i32, i32)> {
+        let tick_spacing = i32::from(pool.tick_spacing);
+        let (min_tick, max_tick) = full_range_tick_indexes(pool.tick_spacing);
+        let (tick_lower_index, tick_upper_index) = match *self {
+            TickRange::Ticks {
+                tick_lower_index,
+                tick_upper_index,
+            } => (
+                tick_with_spacing(tick_lower_index, tick_spacing),
+                tick_with_spacing(tick_upper_index, tick_spacing),
+            ),
+            TickRange::PercentAroundPrice(percent) => {
+                if !(percent > 0.0 && percent < 100.0) {
+                    return Err(ClientError::InvalidArgument(format!(
+                        "range percentage {} is not between 0 and 100",
+                        percent
+                    )));
+                }
+                // the sqrt price moves by the square root of the price ratio
+                let tick_at_ratio = |ratio: f64| -> Result<i32> {
+                    let sqrt_price_x64 = (pool.sqrt_price_x64 as f64 * ratio.sqrt()) as u128;
+                    Ok(tick_with_spacing(
+                        tick_math::get_tick_at_sqrt_price(sqrt_price_x64)?,
+                        tick_spacing,
+                    ))
+                };
+                (
+                    tick_at_ratio(1.0 - percent / 100.0)?,
+                    tick_at_ratio(1.0 + percent / 100.0)?,
+                )
+            }
+            TickRange::TickSpacings(count) => {
+                let current = tick_with_spacing(pool.tick_current, tick_spacing);
+                let width = i64::from(count) * i64::from(tick_spacing);
+                (
+                    (i64::from(current) - width).max(min_tick.into()) as i32,
+                    (i64::from(current) + i64::from(tick_spacing) + width).min(max_tick.into())
+                        as i32,
+                )
+            }
+            TickRange::FullRange => (min_tick, max_tick),
+        };
+        let tick_lower_index = tick_lower_index.max(min_tick);
+        let tick_upper_index = tick_upper_index.min(max_tick);
+        if tick_lower_index >= tick_upper_index {
+            return Err(ClientError::InvalidArgument(format!(
+                "empty tick range {} - {} at tick spacing {}",
+                tick_lower_index, tick_upper_index, tick_spacing
+            )));
+        }
+        Ok((tick_lower_index, tick_upper_index))
+    }
+}
+
+/// Returns the lowest and highest ticks that are multiples of `tick_spacing`.
+pub fn full_range_tick_indexes(tick_spacing: u16) -> (i32, i32) {
+    let max_tick = tick_math::MAX_TICK / i32::from(tick_spacing) * i32::from(tick_spacing);
+    (-max_tick, max_tick)
+}
+
+/// Converts a price range to tick indexes snapped to the pool's tick spacing.
+pub fn price_range_to_tick_indexes(
+    pool: &PoolState,
+    tick_lower_price: &str,
+    tick_upper_price: &str,
+    inverted: bool,
+) -> Result<(i32, i32)> {
+    price_range_to_ticks(
+        tick_lower_price,
+        tick_upper_price,
+        pool.mint_decimals_0,
+        pool.mint_decimals_1,
+        pool.tick_spacing,
+        inverted,
+    )
+}
+
+/// Converts a price range into tick indexes rounded down to multiples of `tick_spacing`.
+///
+/// Inverted prices are in token_0 per token_1, so their lower bound is the upper tick.
+pub fn price_range_to_ticks(
+    tick_lower_price: &str,
+    tick_upper_price: &str,
+    decimals_0: u8,
+    decimals_1: u8,
+    tick_spacing: u16,
+    inverted: bool,
+) -> Result<(i32, i32)> {
+    let tick_at_price = |price: &str| -> Result<i32> {
+        let sqrt_price_x64 =
+            quoted_price_to_sqrt_price_x64(price, decimals_0, decimals_1, inverted)?;
+        Ok(tick_with_spacing(
+            tick_math::get_tick_at_sqrt_price(sqrt_price_x64)?,
+            tick_spacing.into(),
+        ))
+    };
+    if inverted {
+        Ok((
+            tick_at_price(tick_upper_price)?,
+            tick_at_price(tick_lower_price)?,
+        ))
+    } else {
+        Ok((
+            tick_at_price(tick_lower_price)?,
+            tick_at_price(tick_upper_price)?,
+        ))
+    }
+}
+
+/// Finds the payer's position in `pool_pubkey` addressed by `selector`, together with
+/// the token account holding its NFT.
+///
+/// Returns `None` when no position matches a price range. A position NFT that does not
+/// exist, belongs to another pool or is not held by `owner` is an error.
+pub fn find_position(
+    client: &RpcClient,
+    owner: &Pubkey,
+    raydium_amm_v3_program: &Pubkey,
+    pool_pubkey: &Pubkey,
+    pool: &PoolState,
+    selector: &PositionSelector,
+) -> Result<Option<(PersonalPositionState, PositionNftTokenInfo)>> {
+    match *selector {
+        PositionSelector::NftMint(nft_mint) => {
+            let (position_key, _) = Pubkey::find_program_address(
+                &[POSITION_SEED.as_bytes(), nft_mint.to_bytes().as_ref()],
+                raydium_amm_v3_program,
+            );
+            let position: PersonalPositionState = get_anchor_account(client, &position_key)
+                .map_err(|e| match e {
+                    ClientError::AccountNotFound(_) => {
+                        ClientError::PositionNotFound(format!("no position for nft {}", nft_mint))
+                    }
+                    e => e,
+                })?;
+            if position.pool_id != *pool_pubkey {
+                return Err(ClientError::InvalidArgument(format!(
+                    "position nft {} belongs to pool {}, not {}",
+                    nft_mint, position.pool_id, pool_pubkey
+                )));
+            }
+            let nft_info =
+                get_all_nft_and_position_by_owner(client, owner, raydium_amm_v3_program)?
+                    .into_iter()
+                    .find(|nft_info| nft_info.mint == nft_mint)
+                    .ok_or_else(|| {
+                        ClientError::PositionNotFound(format!(
+                            "position nft {} is not held by {}",
+                            nft_mint, owner
+                        ))
+                    })?;
+            Ok(Some((position, nft_info)))
+        }
+        PositionSelector::PriceRange { .. } | PositionSelector::Range(_) => {
+            let tick_range = selector.tick_indexes(pool)?;
+            Ok(
+                get_pool_positions_by_owner(client, owner, raydium_amm_v3_program, pool_pubkey)?
+                    .into_iter()
+                    .find(|(position, _)| {
+                        tick_range == Some((position.tick_lower_index, position.tick_upper_index))
+                    }),
+            )
+        }
+    }
+}
+
+/// Returns every position `owner` holds in `pool_pubkey`, together with the token
+/// account holding its NFT.
+pub fn get_pool_positions_by_owner(
+    client: &RpcClient,
+    owner: &Pubkey,
+    raydium_amm_v3_program: &Pubkey,
+    pool_pubkey: &Pubkey,
+) -> Result<Vec<(PersonalPositionState, PositionNftTokenInfo)>> {
+    let position_nft_infos =
+        get_all_nft_and_position_by_owner(client, owner, raydium_amm_v3_program)?;
+    let positions: Vec<Pubkey> = position_nft_infos
+        .iter()
+        .map(|item| item.position)
+        .collect();
+    let rsps = get_multiple_accounts_chunked(client, &positions)?;
+    let mut pool_positions = Vec::new();
+    for (nft_info, rsp) in position_nft_infos.into_iter().zip(rsps) {
+        let Some(rsp) = rsp else {
+            continue;
+        };
+        let position = deserialize_anchor_account::<PersonalPositionState>(&rsp)?;
+        if position.pool_id == *pool_pubkey {
+            pool_positions.push((position, nft_info));
+        }
+    }
+    Ok(pool_positions)
 }
@@ -0,0 +1,97 @@
+use rand::rngs::OsRng;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction, program_pack::Pack, pubkey::Pubkey, signature::Keypair,
+    signer::Signer, system_instruction,
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token::native_mint;
+
+use crate::{config::Config, error::Result};
+
+/// Returns whether `mint` is the wrapped SOL mint.
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == native_mint::id()
+}
+
+/// A temporary wrapped SOL token account standing in for the payer's WSOL associated
+/// token account during a single transaction.
+///
+/// The account is created and funded with native SOL before the operation and closed
+/// after it, returning its whole balance, including the rent, to the owner.
+pub struct WsolAccount {
+    pub keypair: Keypair,
+    /// Lamports wrapped on top of the rent exemption.
+    pub lamports: u64,
+}
+
+impl WsolAccount {
+    pub fn new(lamports: u64) -> Self {
+        Self {
+            keypair: Keypair::generate(&mut OsRng),
+            lamports,
+        }
+    }
+
+    /// Returns a temporary account wrapping `lamports` when `mint` is the native mint
+    /// and wrapping is enabled in the config.
+    pub fn for_mint(config: &Config, mint: &Pubkey, lamports: u64) -> Option<Self> {
+        (config.global.wrap_sol && is_native_mint(mint)).then(|| Self::new(lamports))
+    }
+
+    pub fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    /// Instructions creating the account, funding it and syncing its token balance.
+    pub fn wrap_instrs(
+        &self,
+        rpc_client: &RpcClient,
+        payer: &Pubkey,
+        owner: &Pubkey,
+    ) -> Result<Vec<Instruction>> {
+        let space = spl_token::state::Account::LEN;
+        let rent = rpc_client.get_minimum_balance_for_rent_exemption(space)?;
+        Ok(vec![
+            system_instruction::create_account(
+                payer,
+                &self.pubkey(),
+                rent + self.lamports,
+                space as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_account3(
+                &spl_token::id(),
+                &self.pubkey(),
+                &native_mint::id(),
+                owner,
+            )?,
+            spl_token::instruction::sync_native(&spl_token::id(), &self.pubkey())?,
+        ])
+    }
+
+    /// Instruction closing the account and unwrapping its balance to `owner`.
+    pub fn unwrap_instr(&self, owner: &Pubkey) -> Result<Instruction> {
+        Ok(spl_token::instruction::close_account(
+            &spl_token::id(),
+            &self.pubkey(),
+            owner,
+            owner,
+            &[],
+        )?)
+    }
+}
+
+/// Returns the token account of `owner` for `mint`: the temporary WSOL account when one
+/// is used for the native mint, the associated token account otherwise.
+pub fn user_token_account(
+    wsol: Option<&WsolAccount>,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Pubkey {
+    match wsol {
+        Some(wsol) if is_native_mint(mint) => wsol.pubkey(),
+        _ => get_associated_token_address_with_program_id(owner, mint, token_program),
+    }
+}
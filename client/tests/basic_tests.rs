@@ -1,9 +1,15 @@
 // tests/test.rs
 
 use client::{
-    config::Config, create_mint, create_pool, create_token_account, decrease_liquidity,
-    increase_liquidity, mint_to_token_account,
+    collect,
+    config::Config,
+    create_mint, create_pool, create_token_account, decrease_liquidity, increase_liquidity,
+    mint_to_token_account, rebalance, swap,
+    utils::{unpack_mint, PositionSelector, TickRange},
+    DepositAmount, MintExtension, MintOptions, WithdrawAmount,
 };
+use solana_sdk::signer::Signer;
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType};
 use std::path::PathBuf;
 
 /// Helper function to load the test configuration file.
@@ -19,7 +25,7 @@ fn load_config() -> Config {
 #[test]
 fn test_mint_token() {
     let config = load_config();
-    let mint = create_mint(&config).expect("Failed to create mint");
+    let mint = create_mint(&config, &MintOptions::default()).expect("Failed to create mint");
     println!("Created mint: {}", mint);
 
     // Assert that the mint is not an empty string.
@@ -27,11 +33,58 @@ fn test_mint_token() {
     assert!(!mint_str.is_empty(), "Mint should not be an empty string");
 }
 
+/// Test creating a Token-2022 mint with decimals and extensions.
+#[test]
+fn test_mint_token_with_extensions() {
+    let config = load_config();
+    let options = MintOptions {
+        decimals: 6,
+        extensions: vec![
+            MintExtension::TransferFee {
+                basis_points: 100,
+                maximum_fee: 1_000_000,
+            },
+            MintExtension::MetadataPointer {
+                metadata_address: None,
+            },
+        ],
+        ..MintOptions::default()
+    };
+    let mint = create_mint(&config, &options).expect("Failed to create mint");
+    println!("Created mint: {}", mint);
+
+    let account = config
+        .rpc_client()
+        .expect("Failed to create RPC client")
+        .get_account(&mint)
+        .expect("Failed to fetch mint");
+    assert_eq!(account.owner, spl_token_2022::id());
+    let state = unpack_mint(&mint, &account).expect("Failed to unpack mint");
+    assert_eq!(state.base.decimals, 6);
+    let extension_types = state
+        .get_extension_types()
+        .expect("Failed to read mint extensions");
+    assert!(extension_types.contains(&ExtensionType::TransferFeeConfig));
+    assert!(extension_types.contains(&ExtensionType::MetadataPointer));
+}
+
+/// Test that extensions are rejected for classic SPL Token mints.
+#[test]
+fn test_mint_token_spl_rejects_extensions() {
+    let config = load_config();
+    let options = MintOptions {
+        token_program: spl_token::id(),
+        extensions: vec![MintExtension::NonTransferable],
+        ..MintOptions::default()
+    };
+    assert!(create_mint(&config, &options).is_err());
+}
+
 /// Test creating a token account for a given mint.
 #[test]
 fn test_create_token_account() {
     let config = load_config();
-    let mint = create_mint(&config).expect("Failed to create mint");
+    let mint = create_mint(&config, &MintOptions::default()).expect("Failed to create mint");
     let token_account =
         create_token_account(&config, &mint).expect("Failed to create token account");
     println!("Created token account: {}", token_account);
@@ -48,7 +101,7 @@ fn test_create_token_account() {
 #[test]
 fn test_mint_to_token_account() {
     let config = load_config();
-    let mint = create_mint(&config).expect("Failed to create mint");
+    let mint = create_mint(&config, &MintOptions::default()).expect("Failed to create mint");
     let token_account =
         create_token_account(&config, &mint).expect("Failed to create token account");
 
@@ -59,22 +112,47 @@ fn test_mint_to_token_account() {
     println!("Minted tokens to token account: {}", token_account);
 }
 
+/// Test the token account and mint-to flows with a classic SPL Token mint.
+#[test]
+fn test_mint_to_spl_token_account() {
+    let config = load_config();
+    let options = MintOptions {
+        token_program: spl_token::id(),
+        ..MintOptions::default()
+    };
+    let mint = create_mint(&config, &options).expect("Failed to create mint");
+    let token_account =
+        create_token_account(&config, &mint).expect("Failed to create token account");
+    assert_eq!(
+        token_account,
+        spl_associated_token_account::get_associated_token_address(
+            &client::utils::read_keypair_file(&config.global.payer_path)
+                .unwrap()
+                .pubkey(),
+            &mint,
+        )
+    );
+
+    mint_to_token_account(&config, &mint, &token_account, 1000)
+        .expect("Failed to mint to token account");
+}
+
 /// Test creating a new pool.
 #[test]
 fn test_create_pool() {
     let config = load_config();
 
-    let mint1 = create_mint(&config).expect("Failed to create mint");
+    let mint1 = create_mint(&config, &MintOptions::default()).expect("Failed to create mint");
     let token_account1 =
         create_token_account(&config, &mint1).expect("Failed to create token account");
 
-    let mint2 = create_mint(&config).expect("Failed to create mint");
+    let mint2 = create_mint(&config, &MintOptions::default()).expect("Failed to create mint");
     let token_account2 =
         create_token_account(&config, &mint2).expect("Failed to create token account");
 
     let config_index = 0;
     let open_time = 0;
-    let price = 10.0;
+    let price = "10";
 
     // Attempt to mint tokens into the account.
     mint_to_token_account(&config, &mint1, &token_account1, 1000)
@@ -98,17 +176,17 @@ fn test_liquidity_operations() {
     // Creating pool
     let config = load_config();
 
-    let mint1 = create_mint(&config).expect("Failed to create mint");
+    let mint1 = create_mint(&config, &MintOptions::default()).expect("Failed to create mint");
     let token_account1 =
         create_token_account(&config, &mint1).expect("Failed to create token account");
 
-    let mint2 = create_mint(&config).expect("Failed to create mint");
+    let mint2 = create_mint(&config, &MintOptions::default()).expect("Failed to create mint");
     let token_account2 =
         create_token_account(&config, &mint2).expect("Failed to create token account");
 
     let config_index = 0;
     let open_time = 0;
-    let price = 10.0;
+    let price = "10";
 
     // Attempt to mint tokens into the account.
     mint_to_token_account(&config, &mint1, &token_account1, 100_000)
@@ -123,46 +201,54 @@ fn test_liquidity_operations() {
 
     // Increasing liquidity
 
-    let tick_lower_price = 1.0;
-    let tick_upper_price = 100.0;
+    let position = PositionSelector::PriceRange {
+        tick_lower_price: "1".to_string(),
+        tick_upper_price: "100".to_string(),
+        quote_mint: None,
+    };
     let input_amount = 100;
 
-    increase_liquidity(
+    let nft_mint = increase_liquidity(
         &config,
-        tick_lower_price,
-        tick_upper_price,
-        true, // Example flag (could be direction or a boolean option)
-        input_amount,
+        position.clone(),
+        DepositAmount::Token0(input_amount),
         pool,
         config.global.slippage,
+        None,
     )
     .expect("Failed to increase liquidity");
 
     println!("Waiting for liquidity to be added to the pool...");
     std::thread::sleep(std::time::Duration::from_secs(30));
 
-    increase_liquidity(
+    // Adding to the same position, this time selected by its NFT.
+    let same_nft_mint = increase_liquidity(
         &config,
-        tick_lower_price,
-        tick_upper_price,
-        true, // Example flag (could be direction or a boolean option)
-        input_amount,
+        PositionSelector::NftMint(nft_mint),
+        DepositAmount::Token0(input_amount),
         pool,
         config.global.slippage,
+        None,
     )
     .expect("Failed to increase liquidity");
+    assert_eq!(nft_mint, same_nft_mint);
+
+    // Collecting fees leaves the liquidity in place.
+    let collected = collect(&config, pool, Some(nft_mint)).expect("Failed to collect fees");
+    assert_eq!(collected.len(), 1);
+    assert_eq!(collected[0].nft_mint, nft_mint);
 
     // Decreasing liquidity
 
-    let liquidity_to_decrease = Some(10);
+    let liquidity_to_decrease = WithdrawAmount::Liquidity(10);
 
     decrease_liquidity(
         &config,
-        tick_lower_price,
-        tick_upper_price,
+        position,
         liquidity_to_decrease,
         pool,
         config.global.slippage,
+        |_| true,
     )
     .expect("Failed to decrease liquidity");
 
@@ -173,11 +259,139 @@ fn test_liquidity_operations() {
 
     decrease_liquidity(
         &config,
-        tick_lower_price,
-        tick_upper_price,
-        None,
+        PositionSelector::NftMint(nft_mint),
+        WithdrawAmount::All,
         pool,
         config.global.slippage,
+        |_| true,
     )
     .expect("Failed to decrease liquidity");
 }
+
+/// Test swapping in both directions with exact-in and exact-out amounts.
+#[test]
+fn test_swap() {
+    let config = load_config();
+
+    let mint1 = create_mint(&config, &MintOptions::default()).expect("Failed to create mint");
+    let token_account1 =
+        create_token_account(&config, &mint1).expect("Failed to create token account");
+
+    let mint2 = create_mint(&config, &MintOptions::default()).expect("Failed to create mint");
+    let token_account2 =
+        create_token_account(&config, &mint2).expect("Failed to create token account");
+
+    mint_to_token_account(&config, &mint1, &token_account1, 1_000_000)
+        .expect("Failed to mint to token account");
+    mint_to_token_account(&config, &mint2, &token_account2, 1_000_000)
+        .expect("Failed to mint to token account");
+
+    let pool = create_pool(&config, 0, "10", mint1, mint2, 0).expect("Failed to create pool");
+
+    increase_liquidity(
+        &config,
+        PositionSelector::PriceRange {
+            tick_lower_price: "1".to_string(),
+            tick_upper_price: "100".to_string(),
+            quote_mint: None,
+        },
+        DepositAmount::Token0(100_000),
+        pool,
+        config.global.slippage,
+        None,
+    )
+    .expect("Failed to increase liquidity");
+
+    println!("Waiting for liquidity to be added to the pool...");
+    std::thread::sleep(std::time::Duration::from_secs(30));
+
+    // Exact input, selling mint1.
+    swap(
+        &config,
+        pool,
+        mint1,
+        100,
+        true,
+        None,
+        None,
+        config.global.slippage,
+    )
+    .expect("Failed to swap exact input");
+
+    // Exact output, buying mint1 back with mint2.
+    swap(
+        &config,
+        pool,
+        mint2,
+        10,
+        false,
+        None,
+        None,
+        config.global.slippage,
+    )
+    .expect("Failed to swap exact output");
+}
+
+/// Test moving a position to a new range, with and without swapping the proceeds.
+#[test]
+fn test_rebalance() {
+    let config = load_config();
+
+    let mint1 = create_mint(&config, &MintOptions::default()).expect("Failed to create mint");
+    let token_account1 =
+        create_token_account(&config, &mint1).expect("Failed to create token account");
+
+    let mint2 = create_mint(&config, &MintOptions::default()).expect("Failed to create mint");
+    let token_account2 =
+        create_token_account(&config, &mint2).expect("Failed to create token account");
+
+    mint_to_token_account(&config, &mint1, &token_account1, 1_000_000)
+        .expect("Failed to mint to token account");
+    mint_to_token_account(&config, &mint2, &token_account2, 1_000_000)
+        .expect("Failed to mint to token account");
+
+    let pool = create_pool(&config, 0, "10", mint1, mint2, 0).expect("Failed to create pool");
+
+    let nft_mint = increase_liquidity(
+        &config,
+        PositionSelector::Range(TickRange::PercentAroundPrice(50.0)),
+        DepositAmount::Token0(100_000),
+        pool,
+        config.global.slippage,
+        None,
+    )
+    .expect("Failed to increase liquidity");
+
+    println!("Waiting for liquidity to be added to the pool...");
+    std::thread::sleep(std::time::Duration::from_secs(30));
+
+    let rebalanced = rebalance(
+        &config,
+        PositionSelector::NftMint(nft_mint),
+        PositionSelector::Range(TickRange::PercentAroundPrice(10.0)),
+        pool,
+        false,
+        config.global.slippage,
+    )
+    .expect("Failed to rebalance");
+    assert_eq!(rebalanced.old_nft_mint, nft_mint);
+    assert_ne!(rebalanced.nft_mint, nft_mint);
+
+    println!("Waiting for the position to be moved...");
+    std::thread::sleep(std::time::Duration::from_secs(30));
+
+    // a range above the price holds only token_0, so the token_1 is swapped
+    rebalance(
+        &config,
+        PositionSelector::NftMint(rebalanced.nft_mint),
+        PositionSelector::PriceRange {
+            tick_lower_price: "20".to_string(),
+            tick_upper_price: "40".to_string(),
+            quote_mint: None,
+        },
+        pool,
+        true,
+        config.global.slippage,
+    )
+    .expect("Failed to rebalance with a swap");
+}
@@ -0,0 +1,13 @@
+// tests/common/mod.rs
+
+use raydium_amm_v3::libraries::tick_math;
+use raydium_amm_v3::states::PoolState;
+
+/// Helper building a pool with `tick_spacing` whose price is at the start of `tick`.
+pub fn pool_at_tick(tick: i32, tick_spacing: u16) -> PoolState {
+    let mut pool = PoolState::default();
+    pool.tick_spacing = tick_spacing;
+    pool.tick_current = tick;
+    pool.sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(tick).unwrap();
+    pool
+}
@@ -0,0 +1,159 @@
+// tests/config_tests.rs
+
+use client::config::{Config, GlobalOverrides, ENV_HTTP_URL, ENV_SLIPPAGE, ENV_WS_URL};
+use std::collections::HashMap;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+const CONFIG: &str = r#"
+[global]
+http_url = "https://api.devnet.solana.com"
+ws_url = "wss://api.devnet.solana.com/"
+payer_path = "~/.config/solana/id.json"
+raydium_v3_program = "devi51mZmdwUJGU9hjN27vEz64Gps7uUefqxg27EAtH"
+slippage = 0.01
+
+[profiles.mainnet]
+http_url = "https://api.mainnet-beta.solana.com"
+raydium_v3_program = "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK"
+"#;
+
+/// Helper function writing a configuration file, deleted when the returned handle drops.
+fn write_config(content: &str) -> NamedTempFile {
+    let mut file = NamedTempFile::new().expect("Failed to create config file");
+    file.write_all(content.as_bytes())
+        .expect("Failed to write config file");
+    file
+}
+
+/// Test that a profile and command-line overrides are layered over the global section.
+#[test]
+fn test_load_profile_and_overrides() {
+    let file = write_config(CONFIG);
+
+    let config = Config::load(file.path(), Some("mainnet"), &GlobalOverrides::default())
+        .expect("Failed to load config with profile");
+    assert_eq!(
+        config.global.http_url,
+        "https://api.mainnet-beta.solana.com"
+    );
+    assert_eq!(config.global.ws_url, "wss://api.devnet.solana.com/");
+    assert_eq!(
+        config.global.raydium_v3_program,
+        "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK"
+    );
+
+    let overrides = GlobalOverrides {
+        ws_url: Some("ws://localhost:8900".to_string()),
+        ..Default::default()
+    };
+    let config =
+        Config::load(file.path(), Some("mainnet"), &overrides).expect("Failed to load config");
+    assert_eq!(config.global.ws_url, "ws://localhost:8900");
+
+    assert!(Config::load(file.path(), Some("unknown"), &GlobalOverrides::default()).is_err());
+}
+
+/// Test that an invalid program id or slippage is rejected at load time.
+#[test]
+fn test_validation() {
+    let file = write_config(&CONFIG.replace(
+        "devi51mZmdwUJGU9hjN27vEz64Gps7uUefqxg27EAtH",
+        "not-a-pubkey",
+    ));
+    assert!(Config::from_file(file.path()).is_err());
+
+    let file = write_config(&CONFIG.replace("slippage = 0.01", "slippage = 1.5"));
+    assert!(Config::from_file(file.path()).is_err());
+
+    // a slippage of 1 would accept any output and double every input
+    let file = write_config(&CONFIG.replace("slippage = 0.01", "slippage = 1.0"));
+    assert!(Config::from_file(file.path()).is_err());
+
+    let file = write_config(&CONFIG.replace(
+        "slippage = 0.01",
+        "slippage = 0.01\ncommitment = \"eventually\"",
+    ));
+    assert!(Config::from_file(file.path()).is_err());
+
+    let file = write_config(CONFIG);
+    let overrides = GlobalOverrides {
+        slippage: Some(-0.1),
+        ..Default::default()
+    };
+    assert!(Config::load(file.path(), None, &overrides).is_err());
+}
+
+/// Test that the priority fee section is optional and parsed when present.
+#[test]
+fn test_priority_fee() {
+    let file = write_config(CONFIG);
+    let config = Config::from_file(file.path()).expect("Failed to load config");
+    assert_eq!(config.priority_fee.compute_unit_price, 0);
+    assert_eq!(config.priority_fee.compute_unit_limit, None);
+
+    let file = write_config(&format!(
+        "{}\n[priority_fee]\ncompute_unit_price = 5000\ncompute_unit_limit = 300000\n",
+        CONFIG
+    ));
+    let config = Config::from_file(file.path()).expect("Failed to load config");
+    assert_eq!(config.priority_fee.compute_unit_price, 5000);
+    assert_eq!(config.priority_fee.compute_unit_limit, Some(300_000));
+    assert_eq!(config.priority_fee.compute_unit_margin, 0.1);
+
+    let file = write_config(&format!(
+        "{}\n[priority_fee]\ncompute_unit_margin = -0.5\n",
+        CONFIG
+    ));
+    assert!(Config::from_file(file.path()).is_err());
+}
+
+/// Test that SOL wrapping is enabled by default and can be turned off by an override.
+#[test]
+fn test_wrap_sol() {
+    let file = write_config(CONFIG);
+    let config = Config::from_file(file.path()).expect("Failed to load config");
+    assert!(config.global.wrap_sol);
+
+    let overrides = GlobalOverrides {
+        wrap_sol: Some(false),
+        ..Default::default()
+    };
+    let config = Config::load(file.path(), None, &overrides).expect("Failed to load config");
+    assert!(!config.global.wrap_sol);
+}
+
+/// Test that the environment variables are layered over the profile and read strictly.
+#[test]
+fn test_env_overrides() {
+    let file = write_config(CONFIG);
+    let mut config = Config::load(file.path(), Some("mainnet"), &GlobalOverrides::default())
+        .expect("Failed to load config");
+
+    let vars = HashMap::from([
+        (ENV_WS_URL.to_string(), "ws://localhost:8900".to_string()),
+        (ENV_SLIPPAGE.to_string(), "0.05".to_string()),
+        ("RAYDIUM_UNKNOWN".to_string(), "ignored".to_string()),
+    ]);
+    let overrides = GlobalOverrides::from_vars(&vars).expect("Failed to read env overrides");
+    config.apply_overrides(&overrides);
+    assert_eq!(
+        config.global.http_url,
+        "https://api.mainnet-beta.solana.com"
+    );
+    assert_eq!(config.global.ws_url, "ws://localhost:8900");
+    assert_eq!(config.global.slippage, 0.05);
+
+    let overrides = GlobalOverrides::from_vars(&HashMap::new()).expect("Failed to read env");
+    assert_eq!(overrides.http_url, None);
+    assert_eq!(overrides.slippage, None);
+
+    let vars = HashMap::from([
+        (
+            ENV_HTTP_URL.to_string(),
+            "http://localhost:8899".to_string(),
+        ),
+        (ENV_SLIPPAGE.to_string(), "one percent".to_string()),
+    ]);
+    assert!(GlobalOverrides::from_vars(&vars).is_err());
+}
@@ -0,0 +1,82 @@
+// tests/create_pool_tests.rs
+
+use std::collections::HashMap;
+
+use anchor_lang::AccountSerialize;
+use client::utils::{get_amm_config_key, get_pool_key};
+use client::{check_create_pool, ClientError};
+use raydium_amm_v3::states::AmmConfig;
+use solana_sdk::{account::Account, program_pack::Pack, pubkey::Pubkey};
+
+/// Helper building a classic SPL Token mint account.
+fn mint_account(decimals: u8) -> Account {
+    let mint = spl_token::state::Mint {
+        decimals,
+        is_initialized: true,
+        ..Default::default()
+    };
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint::pack(mint, &mut data).unwrap();
+    Account {
+        lamports: 1,
+        data,
+        owner: spl_token::id(),
+        ..Default::default()
+    }
+}
+
+/// Helper building an AMM config account owned by `program_id`.
+fn amm_config_account(program_id: &Pubkey) -> Account {
+    let mut data = Vec::new();
+    AmmConfig::default().try_serialize(&mut data).unwrap();
+    Account {
+        lamports: 1,
+        data,
+        owner: *program_id,
+        ..Default::default()
+    }
+}
+
+/// Test that valid parameters are accepted with the mints in pool order.
+#[test]
+fn test_check_create_pool() {
+    let program_id = Pubkey::new_unique();
+    let (mint0, mint1) = client::utils::sort_mints(Pubkey::new_unique(), Pubkey::new_unique());
+    let accounts = HashMap::from([
+        (mint0, mint_account(6)),
+        (mint1, mint_account(6)),
+        (
+            get_amm_config_key(0, &program_id),
+            amm_config_account(&program_id),
+        ),
+    ]);
+
+    let params = check_create_pool(&program_id, 0, "4", mint1, mint0, &accounts)
+        .expect("Failed to validate parameters");
+    assert_eq!((params.mint0, params.mint1), (mint0, mint1));
+    assert_eq!(params.price, "0.25");
+    assert_eq!(params.token_program_0, spl_token::id());
+    assert!(params.tick < 0);
+}
+
+/// Test that every problem is reported at once.
+#[test]
+fn test_check_create_pool_reports_all_problems() {
+    let program_id = Pubkey::new_unique();
+    let (mint0, mint1) = client::utils::sort_mints(Pubkey::new_unique(), Pubkey::new_unique());
+    let amm_config = get_amm_config_key(1, &program_id);
+    let mut not_a_mint = mint_account(6);
+    not_a_mint.owner = Pubkey::new_unique();
+    let accounts = HashMap::from([
+        (mint0, not_a_mint),
+        (
+            get_pool_key(&amm_config, &mint0, &mint1, &program_id),
+            Account::default(),
+        ),
+    ]);
+
+    match check_create_pool(&program_id, 1, "-1", mint0, mint1, &accounts) {
+        Err(ClientError::Validation(problems)) => assert_eq!(problems.len(), 5),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
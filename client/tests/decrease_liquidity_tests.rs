@@ -0,0 +1,81 @@
+// tests/decrease_liquidity_tests.rs
+
+use client::{liquidity_for_withdrawal, WithdrawAmount, MAX_BASIS_POINTS};
+use raydium_amm_v3::libraries::{liquidity_math, tick_math};
+
+/// Test that every withdrawal target resolves to a share of the position's liquidity.
+#[test]
+fn test_liquidity_for_withdrawal() {
+    let sqrt_price = tick_math::get_sqrt_price_at_tick(0).unwrap();
+    let sqrt_lower = tick_math::get_sqrt_price_at_tick(-600).unwrap();
+    let sqrt_upper = tick_math::get_sqrt_price_at_tick(600).unwrap();
+    let position_liquidity = 1_000_000_000;
+    let withdrawal = |withdraw| {
+        liquidity_for_withdrawal(
+            withdraw,
+            position_liquidity,
+            sqrt_price,
+            sqrt_lower,
+            sqrt_upper,
+        )
+    };
+
+    assert_eq!(withdrawal(WithdrawAmount::All).unwrap(), position_liquidity);
+    assert_eq!(withdrawal(WithdrawAmount::Liquidity(10)).unwrap(), 10);
+    assert_eq!(
+        withdrawal(WithdrawAmount::BasisPoints(2_500)).unwrap(),
+        250_000_000
+    );
+    assert_eq!(withdrawal(WithdrawAmount::BasisPoints(1)).unwrap(), 100_000);
+    assert_eq!(
+        withdrawal(WithdrawAmount::BasisPoints(MAX_BASIS_POINTS)).unwrap(),
+        position_liquidity
+    );
+
+    // withdrawing the liquidity for an amount returns at most that amount
+    let liquidity = withdrawal(WithdrawAmount::Token0(1_000)).unwrap();
+    let (amount_0, _) =
+        liquidity_math::get_delta_amounts_signed(0, sqrt_price, -600, 600, -(liquidity as i128))
+            .unwrap();
+    assert!(amount_0 <= 1_000 && amount_0 >= 999);
+    let liquidity = withdrawal(WithdrawAmount::Token1(1_000)).unwrap();
+    let (_, amount_1) =
+        liquidity_math::get_delta_amounts_signed(0, sqrt_price, -600, 600, -(liquidity as i128))
+            .unwrap();
+    assert!(amount_1 <= 1_000 && amount_1 >= 999);
+}
+
+/// Test that withdrawals of nothing or of more than the position holds are rejected.
+#[test]
+fn test_liquidity_for_withdrawal_errors() {
+    let sqrt_price = tick_math::get_sqrt_price_at_tick(0).unwrap();
+    let sqrt_lower = tick_math::get_sqrt_price_at_tick(-600).unwrap();
+    let sqrt_upper = tick_math::get_sqrt_price_at_tick(600).unwrap();
+    let withdrawal =
+        |withdraw| liquidity_for_withdrawal(withdraw, 1_000, sqrt_price, sqrt_lower, sqrt_upper);
+
+    assert!(withdrawal(WithdrawAmount::BasisPoints(0)).is_err());
+    assert!(withdrawal(WithdrawAmount::BasisPoints(10_001)).is_err());
+    assert!(liquidity_for_withdrawal(
+        WithdrawAmount::BasisPoints(5_000),
+        u128::MAX,
+        sqrt_price,
+        sqrt_lower,
+        sqrt_upper
+    )
+    .is_err());
+    assert!(withdrawal(WithdrawAmount::Liquidity(0)).is_err());
+    assert!(withdrawal(WithdrawAmount::Liquidity(1_001)).is_err());
+    assert!(withdrawal(WithdrawAmount::Token0(u64::MAX)).is_err());
+
+    // above the range the position holds no token_0
+    let above = tick_math::get_sqrt_price_at_tick(1_200).unwrap();
+    assert!(liquidity_for_withdrawal(
+        WithdrawAmount::Token0(10),
+        1_000,
+        above,
+        sqrt_lower,
+        sqrt_upper
+    )
+    .is_err());
+}
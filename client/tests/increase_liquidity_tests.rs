@@ -0,0 +1,72 @@
+// tests/increase_liquidity_tests.rs
+
+use client::{liquidity_for_deposit, DepositAmount};
+use raydium_amm_v3::libraries::tick_math;
+
+/// Test that a two-sided deposit is limited by the scarcer token and names it as base.
+#[test]
+fn test_liquidity_for_deposit() {
+    let sqrt_price = tick_math::get_sqrt_price_at_tick(0).unwrap();
+    let sqrt_lower = tick_math::get_sqrt_price_at_tick(-600).unwrap();
+    let sqrt_upper = tick_math::get_sqrt_price_at_tick(600).unwrap();
+
+    let (liquidity_0, base_0) = liquidity_for_deposit(
+        DepositAmount::Token0(1_000_000),
+        sqrt_price,
+        sqrt_lower,
+        sqrt_upper,
+    );
+    assert!(base_0);
+    let (liquidity_1, base_0) = liquidity_for_deposit(
+        DepositAmount::Token1(2_000_000),
+        sqrt_price,
+        sqrt_lower,
+        sqrt_upper,
+    );
+    assert!(!base_0);
+    assert!(liquidity_0 < liquidity_1);
+
+    // token_0 is the scarcer side, so it limits the liquidity
+    let (liquidity, base_0) = liquidity_for_deposit(
+        DepositAmount::Both {
+            amount_0_max: 1_000_000,
+            amount_1_max: 2_000_000,
+        },
+        sqrt_price,
+        sqrt_lower,
+        sqrt_upper,
+    );
+    assert_eq!((liquidity, base_0), (liquidity_0, true));
+
+    let (liquidity, base_0) = liquidity_for_deposit(
+        DepositAmount::Both {
+            amount_0_max: 4_000_000,
+            amount_1_max: 2_000_000,
+        },
+        sqrt_price,
+        sqrt_lower,
+        sqrt_upper,
+    );
+    assert_eq!((liquidity, base_0), (liquidity_1, false));
+}
+
+/// Test that outside the range only the token the range holds counts.
+#[test]
+fn test_liquidity_for_deposit_out_of_range() {
+    let sqrt_lower = tick_math::get_sqrt_price_at_tick(-600).unwrap();
+    let sqrt_upper = tick_math::get_sqrt_price_at_tick(600).unwrap();
+    let deposit = DepositAmount::Both {
+        amount_0_max: 1_000_000,
+        amount_1_max: 1_000_000,
+    };
+
+    let below = tick_math::get_sqrt_price_at_tick(-1_200).unwrap();
+    let (liquidity, base_0) = liquidity_for_deposit(deposit, below, sqrt_lower, sqrt_upper);
+    assert!(liquidity > 0);
+    assert!(base_0);
+
+    let above = tick_math::get_sqrt_price_at_tick(1_200).unwrap();
+    let (liquidity, base_0) = liquidity_for_deposit(deposit, above, sqrt_lower, sqrt_upper);
+    assert!(liquidity > 0);
+    assert!(!base_0);
+}
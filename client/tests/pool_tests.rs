@@ -0,0 +1,129 @@
+// tests/pool_tests.rs
+
+mod common;
+
+use client::utils::quote_pool;
+use client::{liquidity_distribution, liquidity_distribution_to_csv, summarize_pool};
+use common::pool_at_tick;
+use raydium_amm_v3::states::{AmmConfig, TickArrayState, TickState};
+use solana_sdk::pubkey::Pubkey;
+
+/// Test that the pool summary joins the pool, its config and its vault balances.
+#[test]
+fn test_summarize_pool() {
+    let mut pool_state = pool_at_tick(0, 60);
+    pool_state.token_mint_0 = Pubkey::new_unique();
+    pool_state.token_mint_1 = Pubkey::new_unique();
+    pool_state.mint_decimals_0 = 9;
+    pool_state.mint_decimals_1 = 6;
+    pool_state.liquidity = 1_000;
+    pool_state.reward_infos[1].token_mint = Pubkey::new_unique();
+    pool_state.reward_infos[1].emissions_per_second_x64 = 3 << 64;
+
+    let amm_config = AmmConfig {
+        index: 2,
+        trade_fee_rate: 2_500,
+        tick_spacing: 60,
+        ..Default::default()
+    };
+
+    let summary = summarize_pool(Pubkey::new_unique(), &pool_state, &amm_config, 10, 20)
+        .expect("Failed to summarize pool");
+    assert_eq!(summary.config_index, 2);
+    assert_eq!(summary.trade_fee_percent(), 0.25);
+    // a 1:1 raw price is 1000 token_1 per token_0 with 9 and 6 decimals
+    assert_eq!(summary.price, "1000");
+    assert_eq!((summary.vault_amount_0, summary.vault_amount_1), (10, 20));
+    assert_eq!(summary.quote_mint, pool_state.token_mint_1);
+    assert!((summary.tvl - 3e-5).abs() < 1e-12);
+    assert_eq!(summary.rewards.len(), 1);
+    assert_eq!(summary.rewards[0].emissions_per_second, 3.0);
+}
+
+/// Test that quoting a pool in token_0 inverts its price and values its TVL in token_0.
+#[test]
+fn test_quote_pool() {
+    let mut pool_state = pool_at_tick(0, 60);
+    pool_state.token_mint_0 = Pubkey::new_unique();
+    pool_state.token_mint_1 = Pubkey::new_unique();
+    pool_state.mint_decimals_0 = 9;
+    pool_state.mint_decimals_1 = 6;
+    let summary = summarize_pool(
+        Pubkey::new_unique(),
+        &pool_state,
+        &AmmConfig::default(),
+        10,
+        20,
+    )
+    .expect("Failed to summarize pool");
+
+    let quoted = quote_pool(&summary, Some(&pool_state.token_mint_0)).unwrap();
+    assert_eq!(quoted.quote_mint, pool_state.token_mint_0);
+    assert_eq!(quoted.price, "0.001");
+    assert!((quoted.tvl - 3e-8).abs() < 1e-15);
+    assert_eq!(
+        quote_pool(&summary, Some(&pool_state.token_mint_1)).unwrap(),
+        summary
+    );
+    assert!(quote_pool(&summary, Some(&Pubkey::new_unique())).is_err());
+}
+
+/// Helper building an initialized tick.
+fn tick(tick: i32, liquidity_net: i128) -> TickState {
+    TickState {
+        tick,
+        liquidity_net,
+        liquidity_gross: liquidity_net.unsigned_abs(),
+        ..Default::default()
+    }
+}
+
+/// Test that the active liquidity accumulates the net liquidity of the ticks in order,
+/// across tick arrays.
+#[test]
+fn test_liquidity_distribution() {
+    let pool_state = pool_at_tick(50, 10);
+
+    // positions [-100, 100] with 1000 and [0, 700] with 500
+    let mut lower_tick_array = TickArrayState::default();
+    lower_tick_array.start_tick_index = -600;
+    lower_tick_array.ticks[50] = tick(-100, 1_000);
+    let mut upper_tick_array = TickArrayState::default();
+    upper_tick_array.start_tick_index = 0;
+    upper_tick_array.ticks[0] = tick(0, 500);
+    upper_tick_array.ticks[10] = tick(100, -1_000);
+    let mut far_tick_array = TickArrayState::default();
+    far_tick_array.start_tick_index = 600;
+    far_tick_array.ticks[10] = tick(700, -500);
+
+    let buckets = liquidity_distribution(
+        &pool_state,
+        &[upper_tick_array, far_tick_array, lower_tick_array],
+    )
+    .expect("Failed to compute distribution");
+    let ranges: Vec<(i32, i32, u128, bool)> = buckets
+        .iter()
+        .map(|bucket| {
+            (
+                bucket.tick_lower,
+                bucket.tick_upper,
+                bucket.liquidity,
+                bucket.is_current,
+            )
+        })
+        .collect();
+    assert_eq!(
+        ranges,
+        vec![
+            (-100, 0, 1_000, false),
+            (0, 100, 1_500, true),
+            (100, 700, 500, false),
+        ]
+    );
+    assert!(buckets[0].price_lower.parse::<f64>().unwrap() < 1.0);
+    assert_eq!(buckets[0].price_upper, "1");
+
+    let csv = liquidity_distribution_to_csv(&buckets);
+    assert_eq!(csv.lines().count(), 4);
+    assert!(csv.starts_with("tick_lower,tick_upper,"));
+}
@@ -0,0 +1,115 @@
+// tests/positions_tests.rs
+
+mod common;
+
+use client::{get_position_fees, summarize_position};
+use common::pool_at_tick;
+use raydium_amm_v3::states::{PersonalPositionState, PoolState, TickArrayState, TickState};
+use solana_sdk::pubkey::Pubkey;
+
+const TICK_SPACING: u16 = 10;
+const LIQUIDITY: u128 = 1_000_000_000;
+const Q64: u128 = 1 << 64;
+
+/// Helper building a pool at `tick_current` and a position between ticks -100 and 100
+/// that has 5 token_0 and 7 token_1 of fees already accounted.
+fn load_state(tick_current: i32) -> (PoolState, PersonalPositionState) {
+    let mut pool_state = pool_at_tick(tick_current, TICK_SPACING);
+    pool_state.liquidity = LIQUIDITY;
+    pool_state.fee_growth_global_0_x64 = Q64;
+    pool_state.fee_growth_global_1_x64 = 2 * Q64;
+
+    let position = PersonalPositionState {
+        pool_id: Pubkey::new_unique(),
+        nft_mint: Pubkey::new_unique(),
+        tick_lower_index: -100,
+        tick_upper_index: 100,
+        liquidity: LIQUIDITY,
+        token_fees_owed_0: 5,
+        token_fees_owed_1: 7,
+        ..Default::default()
+    };
+    (pool_state, position)
+}
+
+/// Test that fees accrued while in range are added to the fees already owed.
+#[test]
+fn test_position_fees_in_range() {
+    let (pool_state, position) = load_state(0);
+
+    let fees = get_position_fees(
+        &pool_state,
+        &position,
+        &TickState::default(),
+        &TickState::default(),
+    )
+    .expect("Failed to compute fees");
+    assert_eq!(fees.fees_owed_0, 5 + 1_000_000_000);
+    assert_eq!(fees.fees_owed_1, 7 + 2_000_000_000);
+    assert_eq!(fees.rewards_owed, [0, 0, 0]);
+}
+
+/// Test that fees accrued above the range are not credited to the position.
+#[test]
+fn test_position_fees_out_of_range() {
+    let (pool_state, position) = load_state(200);
+
+    let fees = get_position_fees(
+        &pool_state,
+        &position,
+        &TickState::default(),
+        &TickState::default(),
+    )
+    .expect("Failed to compute fees");
+    assert_eq!(fees.fees_owed_0, 5);
+    assert_eq!(fees.fees_owed_1, 7);
+}
+
+/// Test the summary of a position: amounts, prices and in-range status.
+#[test]
+fn test_summarize_position() {
+    let (pool_state, position) = load_state(0);
+
+    let mut lower_tick_array = TickArrayState::default();
+    lower_tick_array.start_tick_index = -600;
+    lower_tick_array.ticks[50] = TickState {
+        tick: -100,
+        ..Default::default()
+    };
+    let mut upper_tick_array = TickArrayState::default();
+    upper_tick_array.start_tick_index = 0;
+    upper_tick_array.ticks[10] = TickState {
+        tick: 100,
+        ..Default::default()
+    };
+
+    let summary = summarize_position(
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        &position,
+        &pool_state,
+        &lower_tick_array,
+        &upper_tick_array,
+    )
+    .expect("Failed to summarize position");
+    assert!(summary.in_range);
+    assert!(summary.amount_0 > 0 && summary.amount_1 > 0);
+    let price = |price: &str| price.parse::<f64>().unwrap();
+    assert!(price(&summary.price_lower) < price(&summary.price_current));
+    assert!(price(&summary.price_current) < price(&summary.price_upper));
+    assert_eq!(summary.fees_owed_0, 5 + 1_000_000_000);
+    assert!(summary.rewards.is_empty());
+
+    let (pool_state, _) = load_state(100);
+    let summary = summarize_position(
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        &position,
+        &pool_state,
+        &lower_tick_array,
+        &upper_tick_array,
+    )
+    .expect("Failed to summarize position");
+    assert!(!summary.in_range);
+    assert_eq!(summary.amount_0, 0);
+}
@@ -0,0 +1,149 @@
+// tests/price_tests.rs
+
+use client::price::{
+    inverse_price_to_sqrt_price_x64, price_to_sqrt_price_x64, sqrt_price_x64_to_inverse_price,
+    sqrt_price_x64_to_price, tick_to_price,
+};
+use client::utils;
+use raydium_amm_v3::libraries::tick_math;
+use solana_sdk::pubkey::Pubkey;
+
+const Q64: u128 = 1 << 64;
+
+/// Test that exact decimal prices convert to exact sqrt prices.
+#[test]
+fn test_price_to_sqrt_price_x64() {
+    assert_eq!(price_to_sqrt_price_x64("1", 6, 6).unwrap(), Q64);
+    assert_eq!(price_to_sqrt_price_x64("4", 0, 0).unwrap(), 2 * Q64);
+    assert_eq!(price_to_sqrt_price_x64("0.25", 0, 0).unwrap(), Q64 / 2);
+    assert_eq!(price_to_sqrt_price_x64("2.5e-1", 0, 0).unwrap(), Q64 / 2);
+    // 1000 token_1 per token_0 is a raw price of 1 with 9 and 6 decimals
+    assert_eq!(price_to_sqrt_price_x64("1000", 9, 6).unwrap(), Q64);
+    // decimals above 9 no longer overflow
+    assert_eq!(price_to_sqrt_price_x64("1", 255, 255).unwrap(), Q64);
+    assert_eq!(
+        price_to_sqrt_price_x64("0.000000000000000000001", 0, 21).unwrap(),
+        Q64
+    );
+}
+
+/// Test that invalid and out of range prices are rejected.
+#[test]
+fn test_price_to_sqrt_price_x64_errors() {
+    for price in ["", ".", "-1", "0", "0.000", "abc", "1e", "NaN", "inf"] {
+        assert!(
+            price_to_sqrt_price_x64(price, 0, 0).is_err(),
+            "{} is accepted",
+            price
+        );
+    }
+    assert!(price_to_sqrt_price_x64("1e40", 0, 0).is_err());
+    // exponents overflowing an i64 once adjusted are rejected instead of wrapping
+    assert!(price_to_sqrt_price_x64("1e9223372036854775807", 0, 255).is_err());
+    assert!(price_to_sqrt_price_x64("1e-9223372036854775808", 255, 0).is_err());
+    assert!(inverse_price_to_sqrt_price_x64("1e-9223372036854775808", 0, 255).is_err());
+    assert!(price_to_sqrt_price_x64("1", 0, 255).is_err());
+    assert_eq!(price_to_sqrt_price_x64("1", 255, 0).unwrap(), 0);
+}
+
+/// Test that prices printed from sqrt prices convert back to the same sqrt prices.
+#[test]
+fn test_sqrt_price_x64_to_price_round_trip() {
+    assert_eq!(sqrt_price_x64_to_price(Q64, 0, 0), "1");
+    assert_eq!(sqrt_price_x64_to_price(Q64 / 2, 0, 0), "0.25");
+    assert_eq!(sqrt_price_x64_to_price(Q64, 9, 6), "1000");
+    assert_eq!(sqrt_price_x64_to_price(Q64, 6, 9), "0.001");
+
+    for (decimals_0, decimals_1) in [(0, 0), (9, 6), (6, 9), (18, 0), (0, 200)] {
+        for tick in (tick_math::MIN_TICK..=tick_math::MAX_TICK).step_by(9_973) {
+            let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(tick).unwrap();
+            let price = tick_to_price(tick, decimals_0, decimals_1).unwrap();
+            assert_eq!(
+                price_to_sqrt_price_x64(&price, decimals_0, decimals_1).unwrap(),
+                sqrt_price_x64,
+                "tick {} printed as {}",
+                tick,
+                price
+            );
+            for sqrt_price_x64 in [sqrt_price_x64 - 1, sqrt_price_x64 + 1] {
+                let price = sqrt_price_x64_to_price(sqrt_price_x64, decimals_0, decimals_1);
+                assert_eq!(
+                    price_to_sqrt_price_x64(&price, decimals_0, decimals_1).unwrap(),
+                    sqrt_price_x64
+                );
+            }
+        }
+    }
+}
+
+/// Test that prices quoted in token_0 are inverted, and that foreign quote mints are rejected.
+#[test]
+fn test_price_inverted() {
+    let (mint_0, mint_1) = utils::sort_mints(Pubkey::new_unique(), Pubkey::new_unique());
+    assert!(!utils::price_inverted(&mint_0, &mint_1, None).unwrap());
+    assert!(!utils::price_inverted(&mint_0, &mint_1, Some(&mint_1)).unwrap());
+    assert!(utils::price_inverted(&mint_0, &mint_1, Some(&mint_0)).unwrap());
+    assert!(utils::price_inverted(&mint_0, &mint_1, Some(&Pubkey::new_unique())).is_err());
+
+    assert_eq!(
+        utils::quoted_price_to_sqrt_price_x64("4", 0, 0, true).unwrap(),
+        Q64 / 2
+    );
+    assert_eq!(
+        utils::quoted_price_to_sqrt_price_x64("4", 0, 0, false).unwrap(),
+        2 * Q64
+    );
+    assert_eq!(
+        utils::sqrt_price_x64_to_quoted_price(2 * Q64, 0, 0, true).unwrap(),
+        "0.25"
+    );
+    // inverted, the lower price is at the upper tick
+    let (price_lower, price_upper) =
+        utils::tick_range_to_quoted_prices(-100, 100, 0, 0, true).unwrap();
+    assert_eq!(
+        utils::quoted_price_to_sqrt_price_x64(&price_lower, 0, 0, true).unwrap(),
+        tick_math::get_sqrt_price_at_tick(100).unwrap()
+    );
+    assert_eq!(
+        utils::quoted_price_to_sqrt_price_x64(&price_upper, 0, 0, true).unwrap(),
+        tick_math::get_sqrt_price_at_tick(-100).unwrap()
+    );
+    assert_eq!(
+        utils::price_range_to_ticks("0.5", "2", 0, 0, 10, true).unwrap(),
+        utils::price_range_to_ticks("0.5", "2", 0, 0, 10, false).unwrap()
+    );
+}
+
+/// Test that inverse prices convert exactly, and that printed inverse prices convert
+/// back to the same sqrt prices.
+#[test]
+fn test_inverse_price_round_trip() {
+    assert_eq!(inverse_price_to_sqrt_price_x64("1", 6, 6).unwrap(), Q64);
+    assert_eq!(
+        inverse_price_to_sqrt_price_x64("0.25", 0, 0).unwrap(),
+        2 * Q64
+    );
+    // 0.001 token_0 per token_1 is a raw price of 1 with 9 and 6 decimals
+    assert_eq!(inverse_price_to_sqrt_price_x64("0.001", 9, 6).unwrap(), Q64);
+    assert_eq!(sqrt_price_x64_to_inverse_price(Q64, 9, 6).unwrap(), "0.001");
+    assert!(inverse_price_to_sqrt_price_x64("1e-40", 0, 0).is_err());
+    assert_eq!(inverse_price_to_sqrt_price_x64("1e41", 0, 0).unwrap(), 0);
+    assert!(sqrt_price_x64_to_inverse_price(0, 0, 0).is_err());
+
+    for (decimals_0, decimals_1) in [(0, 0), (9, 6), (6, 9), (18, 0), (0, 200)] {
+        for tick in (tick_math::MIN_TICK..=tick_math::MAX_TICK).step_by(9_973) {
+            let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(tick).unwrap();
+            for sqrt_price_x64 in [sqrt_price_x64 - 1, sqrt_price_x64, sqrt_price_x64 + 1] {
+                let price = sqrt_price_x64_to_inverse_price(sqrt_price_x64, decimals_0, decimals_1)
+                    .unwrap();
+                assert_eq!(
+                    inverse_price_to_sqrt_price_x64(&price, decimals_0, decimals_1).unwrap(),
+                    sqrt_price_x64,
+                    "sqrt price {} printed as {}",
+                    sqrt_price_x64,
+                    price
+                );
+            }
+        }
+    }
+}
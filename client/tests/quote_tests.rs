@@ -0,0 +1,141 @@
+// tests/quote_tests.rs
+
+mod common;
+
+use std::collections::HashMap;
+
+use client::QuoteAccounts;
+use common::pool_at_tick;
+use raydium_amm_v3::libraries::tick_math;
+use raydium_amm_v3::states::{AmmConfig, TickArrayBitmapExtension, TickArrayState, TickState};
+use solana_sdk::pubkey::Pubkey;
+
+const TICK_SPACING: u16 = 10;
+const LIQUIDITY: u128 = 1_000_000_000;
+
+/// Helper building an offline snapshot of a pool at tick 0 with a single
+/// position between ticks -100 and 100.
+fn load_accounts() -> QuoteAccounts {
+    let amm_config = AmmConfig {
+        trade_fee_rate: 2500,
+        tick_spacing: TICK_SPACING,
+        ..Default::default()
+    };
+
+    let mut pool_state = pool_at_tick(0, TICK_SPACING);
+    pool_state.liquidity = LIQUIDITY;
+    // tick arrays starting at -600 and 0 are initialized
+    pool_state.tick_array_bitmap[7] = 1 << 63;
+    pool_state.tick_array_bitmap[8] = 1;
+
+    let mut lower_tick_array = TickArrayState::default();
+    lower_tick_array.start_tick_index = -600;
+    lower_tick_array.initialized_tick_count = 1;
+    lower_tick_array.ticks[50] = TickState {
+        tick: -100,
+        liquidity_net: LIQUIDITY as i128,
+        liquidity_gross: LIQUIDITY,
+        ..Default::default()
+    };
+
+    let mut upper_tick_array = TickArrayState::default();
+    upper_tick_array.start_tick_index = 0;
+    upper_tick_array.initialized_tick_count = 1;
+    upper_tick_array.ticks[10] = TickState {
+        tick: 100,
+        liquidity_net: -(LIQUIDITY as i128),
+        liquidity_gross: LIQUIDITY,
+        ..Default::default()
+    };
+
+    let mut tick_arrays = HashMap::new();
+    tick_arrays.insert(-600, lower_tick_array);
+    tick_arrays.insert(0, upper_tick_array);
+
+    QuoteAccounts {
+        pool_pubkey: Pubkey::new_unique(),
+        amm_config,
+        pool_state,
+        tickarray_bitmap_extension: TickArrayBitmapExtension::default(),
+        tick_arrays,
+    }
+}
+
+/// Test an exact-input quote in both directions.
+#[test]
+fn test_quote_exact_input() {
+    let accounts = load_accounts();
+
+    let quote = accounts
+        .quote(true, true, 10_000, None)
+        .expect("Failed to quote token_0 for token_1");
+    assert_eq!(quote.amount_in, 10_000);
+    assert!(quote.amount_out > 0 && quote.amount_out < quote.amount_in);
+    assert_eq!(quote.fee_amount, 25);
+    assert!(quote.sqrt_price_x64_after < quote.sqrt_price_x64_before);
+    assert!(quote.price_impact > 0.0 && quote.price_impact < 0.01);
+    assert_eq!(quote.tick_array_start_indexes, vec![0, -600]);
+
+    let quote = accounts
+        .quote(false, true, 10_000, None)
+        .expect("Failed to quote token_1 for token_0");
+    assert!(quote.amount_out > 0 && quote.amount_out < quote.amount_in);
+    assert!(quote.sqrt_price_x64_after > quote.sqrt_price_x64_before);
+    assert_eq!(quote.tick_array_start_indexes, vec![0]);
+}
+
+/// Test that an exact-output quote requires more input than it returns.
+#[test]
+fn test_quote_exact_output() {
+    let accounts = load_accounts();
+
+    let quote = accounts
+        .quote(true, false, 10_000, None)
+        .expect("Failed to quote exact output");
+    assert_eq!(quote.amount_out, 10_000);
+    assert!(quote.amount_in > quote.amount_out);
+    assert!(quote.fee_amount > 0);
+}
+
+/// Test that the price limit stops the swap before the whole amount is used.
+#[test]
+fn test_quote_price_limit() {
+    let accounts = load_accounts();
+
+    let sqrt_price_limit_x64 = tick_math::get_sqrt_price_at_tick(-10).unwrap();
+    let quote = accounts
+        .quote(true, true, 100_000_000, Some(sqrt_price_limit_x64))
+        .expect("Failed to quote with a price limit");
+    assert_eq!(quote.sqrt_price_x64_after, sqrt_price_limit_x64);
+    assert!(quote.amount_in < 100_000_000);
+}
+
+/// Test that a swap running past all loaded liquidity fails instead of returning a bogus quote.
+#[test]
+fn test_quote_out_of_liquidity() {
+    let accounts = load_accounts();
+
+    assert!(accounts.quote(true, true, u64::MAX / 2, None).is_err());
+}
+
+/// Test that withdrawn liquidity no longer absorbs the quoted swap.
+#[test]
+fn test_quote_after_removing_liquidity() {
+    let mut accounts = load_accounts();
+    let before = accounts.quote(true, true, 10_000, None).unwrap();
+
+    accounts.remove_liquidity(-100, 100, LIQUIDITY / 2);
+    assert_eq!(accounts.pool_state.liquidity, LIQUIDITY / 2);
+    assert_eq!(
+        accounts.tick_arrays[&-600].ticks[50].liquidity_net,
+        (LIQUIDITY / 2) as i128
+    );
+    assert_eq!(
+        accounts.tick_arrays[&0].ticks[10].liquidity_net,
+        -((LIQUIDITY / 2) as i128)
+    );
+
+    let after = accounts.quote(true, true, 10_000, None).unwrap();
+    assert!(after.amount_out < before.amount_out);
+    assert!(after.sqrt_price_x64_after < before.sqrt_price_x64_after);
+}
@@ -0,0 +1,42 @@
+// tests/rebalance_tests.rs
+
+use client::swap_for_range;
+use raydium_amm_v3::libraries::tick_math;
+
+/// Test that the swap brings the amounts to the ratio of the range at the current price.
+#[test]
+fn test_swap_for_range() {
+    let sqrt_price = tick_math::get_sqrt_price_at_tick(0).unwrap();
+    let sqrt_lower = tick_math::get_sqrt_price_at_tick(-600).unwrap();
+    let sqrt_upper = tick_math::get_sqrt_price_at_tick(600).unwrap();
+
+    // a range centred on the price holds equal values of both tokens
+    let (zero_for_one, amount_in) =
+        swap_for_range(1_000, 0, sqrt_price, sqrt_lower, sqrt_upper).unwrap();
+    assert!(zero_for_one);
+    assert!((499..=501).contains(&amount_in));
+    let (zero_for_one, amount_in) =
+        swap_for_range(0, 1_000, sqrt_price, sqrt_lower, sqrt_upper).unwrap();
+    assert!(!zero_for_one);
+    assert!((499..=501).contains(&amount_in));
+    assert_eq!(
+        swap_for_range(500, 500, sqrt_price, sqrt_lower, sqrt_upper),
+        None
+    );
+
+    // a range below the price holds only token_1, one above it only token_0
+    let sqrt_upper_below = tick_math::get_sqrt_price_at_tick(-60).unwrap();
+    assert_eq!(
+        swap_for_range(1_000, 10, sqrt_price, sqrt_lower, sqrt_upper_below),
+        Some((true, 1_000))
+    );
+    let sqrt_lower_above = tick_math::get_sqrt_price_at_tick(60).unwrap();
+    assert_eq!(
+        swap_for_range(10, 1_000, sqrt_price, sqrt_lower_above, sqrt_upper),
+        Some((false, 1_000))
+    );
+    assert_eq!(
+        swap_for_range(1_000, 0, sqrt_price, sqrt_lower_above, sqrt_upper),
+        None
+    );
+}
@@ -0,0 +1,68 @@
+// tests/rpc_tests.rs
+
+use client::rpc::{decode_transaction_error, pack_instructions, txn_size};
+use raydium_amm_v3::error::ErrorCode;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::{
+    instruction::InstructionError, pubkey::Pubkey, system_instruction,
+    transaction::TransactionError,
+};
+
+/// Test that a failing instruction is described with the anchor error it logged.
+#[test]
+fn test_decode_transaction_error() {
+    let number = u32::from(ErrorCode::PriceSlippageCheck);
+    let err = TransactionError::InstructionError(1, InstructionError::Custom(number));
+    let logs = vec![
+        "Program devi51mZmdwUJGU9hjN27vEz64Gps7uUefqxg27EAtH invoke [1]".to_string(),
+        format!("Program log: AnchorError occurred. Error Code: PriceSlippageCheck. Error Number: {}. Error Message: Price slippage check.", number),
+        format!("Program devi51mZmdwUJGU9hjN27vEz64Gps7uUefqxg27EAtH failed: custom program error: {:#x}", number),
+    ];
+    let expected = format!(
+        "Error Code: PriceSlippageCheck. Error Number: {}. Error Message: Price slippage check.",
+        number
+    );
+
+    let decoded = decode_transaction_error(&err, &logs);
+    assert!(decoded.starts_with(&expected));
+
+    // Without logs, the error number is looked up in the program's error codes.
+    let decoded = decode_transaction_error(&err, &logs[..1]);
+    assert!(decoded.starts_with(&expected));
+    assert!(decode_transaction_error(&err, &[]).starts_with(&expected));
+
+    // Other errors are kept as they are.
+    let err = TransactionError::InstructionError(0, InstructionError::Custom(1));
+    assert_eq!(decode_transaction_error(&err, &[]), err.to_string());
+    let err = TransactionError::InstructionError(0, InstructionError::InvalidArgument);
+    assert_eq!(decode_transaction_error(&err, &[]), err.to_string());
+}
+
+/// Test that instruction groups are merged while they fit in a packet, without being split.
+#[test]
+fn test_pack_instructions() {
+    let payer = Pubkey::new_unique();
+    // every transfer adds a new recipient to the transaction
+    let group = |size: usize| -> Vec<_> {
+        (0..size)
+            .map(|_| system_instruction::transfer(&payer, &Pubkey::new_unique(), 1))
+            .collect()
+    };
+
+    let packed = pack_instructions(vec![group(2), vec![], group(3)], &payer);
+    assert_eq!(packed.len(), 1);
+    assert_eq!(packed[0].len(), 5);
+
+    let packed = pack_instructions(vec![group(15), group(15), group(15)], &payer);
+    assert!(packed.len() > 1);
+    assert_eq!(packed.iter().map(Vec::len).sum::<usize>(), 45);
+    for instructions in &packed {
+        assert_eq!(instructions.len() % 15, 0);
+        assert!(txn_size(instructions, &payer) <= PACKET_DATA_SIZE);
+    }
+
+    // a group larger than a packet is kept whole
+    let packed = pack_instructions(vec![group(1), group(40)], &payer);
+    assert_eq!(packed.len(), 2);
+    assert_eq!(packed[1].len(), 40);
+}
@@ -0,0 +1,67 @@
+// tests/tick_range_tests.rs
+
+mod common;
+
+use client::utils::{full_range_tick_indexes, PositionSelector, TickRange};
+use common::pool_at_tick;
+
+/// Test that every way of giving a range resolves to ticks aligned to the spacing.
+#[test]
+fn test_tick_range() {
+    let pool = pool_at_tick(130, 60);
+
+    let ticks = TickRange::Ticks {
+        tick_lower_index: -70,
+        tick_upper_index: 250,
+    };
+    assert_eq!(ticks.tick_indexes(&pool).unwrap(), (-120, 240));
+
+    // the current tick lies in the spacing [120, 180)
+    assert_eq!(
+        TickRange::TickSpacings(2).tick_indexes(&pool).unwrap(),
+        (0, 300)
+    );
+
+    // 5% below and above the price of tick 130 are ticks -383 and 617
+    let (lower, upper) = TickRange::PercentAroundPrice(5.0)
+        .tick_indexes(&pool)
+        .unwrap();
+    assert_eq!((lower, upper), (-420, 600));
+
+    let (min_tick, max_tick) = full_range_tick_indexes(60);
+    assert_eq!((min_tick, max_tick), (-443_580, 443_580));
+    assert_eq!(
+        TickRange::FullRange.tick_indexes(&pool).unwrap(),
+        (min_tick, max_tick)
+    );
+    assert_eq!(
+        TickRange::TickSpacings(u32::MAX)
+            .tick_indexes(&pool)
+            .unwrap(),
+        (min_tick, max_tick)
+    );
+
+    assert_eq!(
+        PositionSelector::Range(TickRange::FullRange)
+            .tick_indexes(&pool)
+            .unwrap(),
+        Some((min_tick, max_tick))
+    );
+}
+
+/// Test that empty and invalid ranges are rejected.
+#[test]
+fn test_tick_range_errors() {
+    let pool = pool_at_tick(0, 10);
+    let empty = TickRange::Ticks {
+        tick_lower_index: 15,
+        tick_upper_index: 19,
+    };
+    assert!(empty.tick_indexes(&pool).is_err());
+    assert!(TickRange::PercentAroundPrice(0.0)
+        .tick_indexes(&pool)
+        .is_err());
+    assert!(TickRange::PercentAroundPrice(100.0)
+        .tick_indexes(&pool)
+        .is_err());
+}